@@ -1,6 +1,7 @@
 use eskom_se_push_api::area_info::{AreaInfo, AreaInfoURLBuilder};
 use eskom_se_push_api::{
-  constants::TOKEN_KEY, get_token_from_env, ureq_client::handle_ureq_response, Endpoint,
+  constants::TOKEN_KEY, get_base_url_from_env, get_token_from_env,
+  ureq_client::handle_ureq_response, Endpoint,
 };
 
 fn main() {
@@ -8,6 +9,7 @@ fn main() {
     Ok(val) => {
       let api = AreaInfoURLBuilder::default()
         .area_id("tshwane-6-brooklyn".to_owned())
+        .base_url(get_base_url_from_env(None))
         .build()
         .unwrap();
       // Need to import the Endpoint trait
@@ -1,7 +1,7 @@
 use eskom_se_push_api::{
   area_search::{AreaSearch, AreaSearchURLBuilder},
   constants::TOKEN_KEY,
-  get_token_from_env,
+  get_base_url_from_env, get_token_from_env,
   ureq_client::handle_ureq_response,
   Endpoint,
 };
@@ -11,6 +11,7 @@ fn main() {
     Ok(val) => {
       let api = AreaSearchURLBuilder::default()
         .search_term("brooklyn")
+        .base_url(get_base_url_from_env(None))
         .build()
         .unwrap();
       // Need to import the Endpoint trait
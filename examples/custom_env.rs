@@ -1,7 +1,7 @@
 use eskom_se_push_api::{
   allowance::{AllowanceCheck, AllowanceCheckURLBuilder},
   constants::TOKEN_KEY,
-  get_token_from_env,
+  get_base_url_from_env, get_token_from_env,
   ureq_client::handle_ureq_response,
   Endpoint,
 };
@@ -9,7 +9,10 @@ use eskom_se_push_api::{
 fn main() {
   match get_token_from_env(Some("MY_CUSTOM_KEY")) {
     Ok(val) => {
-      let api = AllowanceCheckURLBuilder::default().build().unwrap();
+      let api = AllowanceCheckURLBuilder::default()
+        .base_url(get_base_url_from_env(None))
+        .build()
+        .unwrap();
       // Need to import the Endpoint trait
       let response = ureq::request(api.method(), api.url().unwrap().as_str())
         .set(TOKEN_KEY, &val)
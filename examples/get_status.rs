@@ -1,6 +1,6 @@
 use eskom_se_push_api::{
   constants::TOKEN_KEY,
-  get_token_from_env,
+  get_base_url_from_env, get_token_from_env,
   status::{EskomStatus, EskomStatusUrlBuilder},
   ureq_client::handle_ureq_response,
   Endpoint,
@@ -9,7 +9,10 @@ use eskom_se_push_api::{
 fn main() {
   match get_token_from_env(None) {
     Ok(val) => {
-      let api = EskomStatusUrlBuilder::default().build().unwrap();
+      let api = EskomStatusUrlBuilder::default()
+        .base_url(get_base_url_from_env(None))
+        .build()
+        .unwrap();
       // Need to import the Endpoint trait
       let response = ureq::request(api.method(), api.url().unwrap().as_str())
         .set(TOKEN_KEY, &val)
@@ -1,10 +1,10 @@
-use eskom_se_push_api::get_token_from_env;
 use eskom_se_push_api::reqwest_blocking_client::ReqwestBlockingCLient;
+use eskom_se_push_api::{get_base_url_from_env, get_token_from_env};
 
 fn main() {
   match get_token_from_env(None) {
     Ok(val) => {
-      let api = ReqwestBlockingCLient::new(val);
+      let api = ReqwestBlockingCLient::new(val).with_base_url(get_base_url_from_env(None));
       match api.get_load_shedding_status() {
         Ok(status) => {
           println!("{:?}", status);
@@ -2,29 +2,32 @@ use derive_builder::Builder;
 use serde::Deserialize;
 use serde::Serialize;
 
-use crate::traits::Endpoint;
 #[cfg(any(feature = "async", doc))]
 use crate::traits::EndpointAsync;
+use crate::traits::{endpoint_url, Endpoint};
 
 /// The URL builder for the Allowance Check endpoint
 /// ```rust
-/// let t = AllowanceCheckURL::default()
+/// use eskom_se_push_api::{allowance::AllowanceCheckURL, Endpoint};
+///
+/// let t = AllowanceCheckURL::default();
 /// // returns the url for built the endpoint
-/// t.url().unwrap()
+/// t.url().unwrap();
 /// ```
 #[derive(Default, Builder, Debug)]
 #[builder(setter(into))]
-pub struct AllowanceCheckURL {}
+pub struct AllowanceCheckURL {
+  /// Overrides the base URL of the API eg a staging proxy or a local mock server.
+  /// Defaults to [`BASE_URL`](crate::constants::BASE_URL) if not set
+  #[builder(default, setter(into, strip_option))]
+  base_url: Option<String>,
+}
 
 impl Endpoint for AllowanceCheckURL {
   type Output = AllowanceCheck;
 
   fn endpoint(&self) -> std::borrow::Cow<'static, str> {
-    std::borrow::Cow::Borrowed("https://developer.sepush.co.za/business/2.0/api_allowance")
-  }
-
-  fn url(&self) -> Result<url::Url, crate::errors::HttpError> {
-    Ok(url::Url::parse(&self.endpoint()).unwrap())
+    endpoint_url(self.base_url.as_deref(), "api_allowance")
   }
 }
 #[cfg(any(feature = "async", doc))]
@@ -1,44 +1,95 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
 use derive_builder::Builder;
 use serde::Deserialize;
 use serde::Serialize;
 
-use crate::errors::HttpError;
-use crate::traits::Endpoint;
+use crate::errors::{HttpError, ScheduleError};
+use crate::schedule::{self, DaySchedule, OutageWindow, StageTimeline};
+use crate::status::Stage;
 #[cfg(any(feature = "async", doc))]
 use crate::traits::EndpointAsync;
+use crate::traits::{endpoint_url, Endpoint};
 
-/// The URL builder for the Allowance Check endpoint
+/// The URL builder for the Area Info endpoint
 /// ```rust
-/// let t = AllowanceCheckURL::default()
+/// use eskom_se_push_api::{area_info::AreaInfoURLBuilder, Endpoint};
+///
+/// let t = AreaInfoURLBuilder::default()
+///   .area_id("tshwane-6-brooklyn".to_owned())
+///   .build()
+///   .unwrap();
 /// // returns the url for built the endpoint
-/// t.url().unwrap()
+/// t.url().unwrap();
+/// ```
+/// Test mode returns synthetic events without using the quota
+/// ```rust
+/// use eskom_se_push_api::{
+///   area_info::{AreaInfoURLBuilder, TestMode},
+///   Endpoint,
+/// };
+///
+/// let t = AreaInfoURLBuilder::default()
+///   .area_id("tshwane-6-brooklyn".to_owned())
+///   .test(TestMode::Current)
+///   .build()
+///   .unwrap();
+/// assert!(t.url().unwrap().as_str().ends_with("&test=current"));
 /// ```
 #[derive(Default, Builder, Debug)]
 #[builder()]
 pub struct AreaInfoURL {
   area_id: String,
+  /// Returns synthetic events without using the quota. Useful for building UIs
+  #[builder(default, setter(strip_option))]
+  test: Option<TestMode>,
+  /// Overrides the base URL of the API eg a staging proxy or a local mock server.
+  /// Defaults to [`BASE_URL`](crate::constants::BASE_URL) if not set
+  #[builder(default, setter(into, strip_option))]
+  base_url: Option<String>,
 }
 
 impl Endpoint for AreaInfoURL {
   type Output = AreaInfo;
 
   fn endpoint(&self) -> std::borrow::Cow<'static, str> {
-    std::borrow::Cow::Borrowed("https://developer.sepush.co.za/business/2.0/area")
+    endpoint_url(self.base_url.as_deref(), "area")
   }
 
-  fn url(&self) -> Result<url::Url, crate::errors::HttpError> {
-    let mut u = url::Url::parse(&self.endpoint()).unwrap();
+  fn query_pairs(&self) -> Result<Vec<(&'static str, String)>, HttpError> {
     if self.area_id.trim().is_empty() {
-      Err(HttpError::AreaIdNotSet)
-    } else {
-      u.set_query(Some(format!("id={}", self.area_id).as_str()));
-      Ok(u)
+      return Err(HttpError::AreaIdNotSet);
+    }
+    let mut pairs = vec![("id", self.area_id.clone())];
+    if let Some(test) = self.test {
+      pairs.push(("test", test.as_str().to_owned()));
     }
+    Ok(pairs)
   }
 }
 #[cfg(any(feature = "async", doc))]
 impl EndpointAsync for AreaInfoURL {}
 
+/// The test modes of the Area Info endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TestMode {
+  /// The area is loadshedding right now
+  Current,
+  /// The area will be loadshedding soon
+  Future,
+}
+
+impl TestMode {
+  /// The value of the `test` query parameter
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      TestMode::Current => "current",
+      TestMode::Future => "future",
+    }
+  }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AreaInfo {
@@ -52,18 +103,146 @@ pub struct AreaInfo {
   pub schedule: Schedule,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+impl AreaInfo {
+  /// Whether the area is loadshedding at the given time
+  pub fn is_active_at<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> bool {
+    self.current_event_at(at).is_some()
+  }
+
+  /// Whether the area is loadshedding right now
+  pub fn is_active(&self) -> bool {
+    self.is_active_at(&Utc::now())
+  }
+
+  /// The event that is happening at the given time
+  pub fn current_event_at<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> Option<&Event> {
+    self.events.iter().find(|event| event.is_active_at(at))
+  }
+
+  /// The event that is happening right now
+  pub fn current_event(&self) -> Option<&Event> {
+    self.current_event_at(&Utc::now())
+  }
+
+  /// The first event that starts after the given time
+  pub fn next_event_at<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> Option<&Event> {
+    self
+      .events
+      .iter()
+      .filter(|event| event.start > *at)
+      .min_by_key(|event| event.start)
+  }
+
+  /// The first event that hasn't started yet
+  pub fn next_event(&self) -> Option<&Event> {
+    self.next_event_at(&Utc::now())
+  }
+
+  /// How long from the given time until the power goes off (the next event starts)
+  /// or comes back on (the current event ends).
+  /// Returns `None` if there are no more events
+  pub fn time_until_next_change_at<Tz: TimeZone>(
+    &self,
+    at: &DateTime<Tz>,
+  ) -> Option<chrono::Duration> {
+    let at = at.with_timezone(&Utc);
+    let change = match self.current_event_at(&at) {
+      Some(event) => event.end,
+      None => self.next_event_at(&at)?.start,
+    };
+    Some(change.with_timezone(&Utc) - at)
+  }
+
+  /// How long until the power goes off (the next event starts) or comes back on (the current event ends).
+  /// Returns `None` if there are no more events
+  pub fn time_until_next_change(&self) -> Option<chrono::Duration> {
+    self.time_until_next_change_at(&Utc::now())
+  }
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "RawEvent", into = "RawEvent")]
 pub struct Event {
-  // TODO convert to Date
   /// End time of the event eg `2022-08-08T22:30:00+02:00`
-  pub end: String,
-  // TODO convert to enum
-  /// The stage of the event eg `Stage 2`
-  pub note: String,
-  // TODO convert to Date
+  pub end: DateTime<FixedOffset>,
+  /// The stage of the event parsed from the note eg `Stage 2`.
+  /// `Note`: Notes that aren't a stage are kept as [`Stage::Unknown`]
+  pub note: Stage,
+  /// The note as the API sent it, see [`Event::raw_note`]
+  raw_note: String,
   /// Start time of the event eg `2022-08-08T20:00:00+02:00`
-  pub start: String,
+  pub start: DateTime<FixedOffset>,
+}
+
+impl Event {
+  /// An event with the note as the API sends it, parsed into the stage
+  pub fn new(
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+    note: impl Into<String>,
+  ) -> Self {
+    let raw_note = note.into();
+    Event {
+      end,
+      note: Stage::from_note(&raw_note),
+      raw_note,
+      start,
+    }
+  }
+
+  /// The note as the API sent it eg `Stage 2 (TESTING: current)`. This is what is serialized.
+  /// Falls back to the stage eg `Stage 4` if `note` has been changed since
+  pub fn raw_note(&self) -> Cow<'_, str> {
+    if Stage::from_note(&self.raw_note) == self.note {
+      Cow::Borrowed(&self.raw_note)
+    } else {
+      Cow::Owned(self.note.to_string())
+    }
+  }
+
+  /// Whether the event is happening at the given time. The start is inclusive and the end exclusive
+  pub fn is_active_at<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> bool {
+    self.start <= *at && self.end > *at
+  }
+
+  /// How long the event lasts
+  pub fn duration(&self) -> chrono::Duration {
+    self.end - self.start
+  }
+}
+
+/// Events are equal if their times and stages are, however the note was written
+impl PartialEq for Event {
+  fn eq(&self, other: &Self) -> bool {
+    self.start == other.start && self.end == other.end && self.note == other.note
+  }
+}
+
+impl Eq for Event {}
+
+/// An [`Event`] as the API sends it, with the note as text
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawEvent {
+  end: DateTime<FixedOffset>,
+  note: String,
+  start: DateTime<FixedOffset>,
+}
+
+impl From<RawEvent> for Event {
+  fn from(event: RawEvent) -> Self {
+    Event::new(event.start, event.end, event.note)
+  }
+}
+
+impl From<Event> for RawEvent {
+  fn from(event: Event) -> Self {
+    RawEvent {
+      end: event.end,
+      note: event.raw_note().into_owned(),
+      start: event.start,
+    }
+  }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -82,13 +261,43 @@ pub struct Schedule {
   pub source: String,
 }
 
+impl Schedule {
+  /// The typed schedule of every day
+  pub fn parse_days(&self) -> Result<Vec<DaySchedule>, ScheduleError> {
+    self.days.iter().map(Day::parse).collect()
+  }
+
+  /// The outage windows of the first `days` days of the schedule if the stage doesn't change.
+  /// Stage 5 upwards falls back to the `Stage 4` schedule if the region doesn't have one for it
+  pub fn project_stage(
+    &self,
+    stage: &Stage,
+    days: usize,
+  ) -> Result<Vec<OutageWindow>, ScheduleError> {
+    self.project(&StageTimeline::constant(stage.clone()), days)
+  }
+
+  /// The outage windows of the first `days` days of the schedule with the stages in the timeline
+  pub fn project(
+    &self,
+    timeline: &StageTimeline,
+    days: usize,
+  ) -> Result<Vec<OutageWindow>, ScheduleError> {
+    let days = self
+      .days
+      .iter()
+      .take(days)
+      .map(Day::parse)
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(schedule::project(&days, timeline))
+  }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Day {
-  // TODO convert to Date
   /// Date the stages are relevant to eg `2022-08-08`
   pub date: String,
-  // TODO convert to enum
   /// Day of week eg `Monday`
   pub name: String,
   /// Raw loadshedding schedule, per stage (1-8).
@@ -99,3 +308,78 @@ pub struct Day {
   ///  * `Note`: Some Municipalities/Regions don't have Stage 5-8 schedules (and there will be 4 records instead of 8 in this list. Stage 5 upwards you can assume Stage 4 schedule impact.
   pub stages: Vec<Vec<String>>,
 }
+
+impl Day {
+  /// The typed schedule of the day with the date, weekday and time ranges parsed
+  pub fn parse(&self) -> Result<DaySchedule, ScheduleError> {
+    DaySchedule::try_from(self)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn area_info() -> AreaInfo {
+    let event = |start: &str, end: &str| {
+      Event::new(
+        DateTime::parse_from_rfc3339(start).unwrap(),
+        DateTime::parse_from_rfc3339(end).unwrap(),
+        "Stage 2",
+      )
+    };
+    AreaInfo {
+      events: vec![
+        event("2022-08-08T20:00:00+02:00", "2022-08-08T22:30:00+02:00"),
+        event("2022-08-09T12:00:00+02:00", "2022-08-09T14:30:00+02:00"),
+      ],
+      ..Default::default()
+    }
+  }
+
+  fn utc(time: &str) -> DateTime<Utc> {
+    time.parse().unwrap()
+  }
+
+  #[test]
+  fn finds_current_and_next_events_across_offsets() {
+    let info = area_info();
+    // 20:30 in South Africa
+    let during = utc("2022-08-08T18:30:00Z");
+    assert!(info.is_active_at(&during));
+    assert_eq!(info.current_event_at(&during), Some(&info.events[0]));
+    assert_eq!(info.next_event_at(&during), Some(&info.events[1]));
+    assert_eq!(
+      info.time_until_next_change_at(&during),
+      Some(chrono::Duration::hours(2))
+    );
+    // The end of an event is exclusive
+    let after = utc("2022-08-08T20:30:00Z");
+    assert!(!info.is_active_at(&after));
+    assert_eq!(
+      info.time_until_next_change_at(&after),
+      Some(chrono::Duration::hours(13) + chrono::Duration::minutes(30))
+    );
+    let done = utc("2022-08-10T00:00:00Z");
+    assert_eq!(info.next_event_at(&done), None);
+    assert_eq!(info.time_until_next_change_at(&done), None);
+  }
+
+  #[test]
+  fn keeps_the_raw_note() {
+    let json = r#"{"end":"2022-08-08T22:30:00+02:00","note":"Stage 2 (TESTING: current)","start":"2022-08-08T20:00:00+02:00"}"#;
+    let event: Event = serde_json::from_str(json).unwrap();
+    assert_eq!(event.note, Stage::Stage2);
+    assert_eq!(event.raw_note(), "Stage 2 (TESTING: current)");
+    assert_eq!(serde_json::to_string(&event).unwrap(), json);
+    assert_eq!(event, Event::new(event.start, event.end, "Stage 2"));
+
+    // The stage is the source of truth once it is changed
+    let mut event = event;
+    event.note = Stage::Stage4;
+    assert_eq!(event.raw_note(), "Stage 4");
+    assert!(serde_json::to_string(&event)
+      .unwrap()
+      .contains(r#""note":"Stage 4""#));
+  }
+}
@@ -2,43 +2,47 @@ use derive_builder::Builder;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::coordinates::Coordinates;
 use crate::errors::HttpError;
-use crate::traits::Endpoint;
 #[cfg(any(feature = "async", doc))]
 use crate::traits::EndpointAsync;
+use crate::traits::{endpoint_url, Endpoint};
 
 #[derive(Default, Builder, Debug)]
 #[builder(setter(into))]
 pub struct AreasNearbyURL {
   latitude: f32,
   longitude: f32,
+  /// Overrides the base URL of the API eg a staging proxy or a local mock server.
+  /// Defaults to [`BASE_URL`](crate::constants::BASE_URL) if not set
+  #[builder(default, setter(into, strip_option))]
+  base_url: Option<String>,
 }
 
 impl Endpoint for AreasNearbyURL {
   type Output = AreaNearby;
 
   fn endpoint(&self) -> std::borrow::Cow<'static, str> {
-    std::borrow::Cow::Borrowed("https://developer.sepush.co.za/business/2.0/areas_nearby")
+    endpoint_url(self.base_url.as_deref(), "areas_nearby")
   }
 
-  fn url(&self) -> Result<url::Url, crate::errors::HttpError> {
-    let mut u = url::Url::parse(&self.endpoint()).unwrap();
-    if self.latitude == 0. || self.longitude == 0. {
-      Err(HttpError::LongitudeOrLatitudeNotSet {
-        longitude: self.longitude,
-        latitude: self.latitude,
-      })
-    } else {
-      u.set_query(Some(format!("lat={}", self.latitude).as_str()));
-      u.set_query(Some(format!("long={}", self.longitude).as_str()));
-      Ok(u)
-    }
+  fn query_pairs(&self) -> Result<Vec<(&'static str, String)>, HttpError> {
+    Ok(Coordinates::new(self.latitude, self.longitude)?.query_pairs())
   }
 }
 
 #[cfg(any(feature = "async", doc))]
 impl EndpointAsync for AreasNearbyURL {}
 
+impl AreasNearbyURLBuilder {
+  /// Sets the latitude and longitude
+  pub fn coordinates(&mut self, coordinates: Coordinates) -> &mut Self {
+    self
+      .latitude(coordinates.latitude())
+      .longitude(coordinates.longitude())
+  }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AreaNearby {
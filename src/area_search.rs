@@ -3,31 +3,32 @@ use serde::Deserialize;
 use serde::Serialize;
 
 use crate::errors::HttpError;
-use crate::traits::Endpoint;
 #[cfg(any(feature = "async", doc))]
 use crate::traits::EndpointAsync;
+use crate::traits::{endpoint_url, Endpoint};
 
 #[derive(Default, Builder, Debug)]
 #[builder(setter(into))]
 pub struct AreaSearchURL {
   search_term: String,
+  /// Overrides the base URL of the API eg a staging proxy or a local mock server.
+  /// Defaults to [`BASE_URL`](crate::constants::BASE_URL) if not set
+  #[builder(default, setter(into, strip_option))]
+  base_url: Option<String>,
 }
 
 impl Endpoint for AreaSearchURL {
   type Output = AreaSearch;
 
   fn endpoint(&self) -> std::borrow::Cow<'static, str> {
-    std::borrow::Cow::Borrowed("https://developer.sepush.co.za/business/2.0/areas_search")
+    endpoint_url(self.base_url.as_deref(), "areas_search")
   }
 
-  fn url(&self) -> Result<url::Url, crate::errors::HttpError> {
-    let mut u = url::Url::parse(&self.endpoint()).unwrap();
+  fn query_pairs(&self) -> Result<Vec<(&'static str, String)>, HttpError> {
     if self.search_term.trim().is_empty() {
-      Err(HttpError::SearchTextNotSet)
-    } else {
-      u.set_query(Some(format!("text={}", self.search_term).as_str()));
-      Ok(u)
+      return Err(HttpError::SearchTextNotSet);
     }
+    Ok(vec![("text", self.search_term.clone())])
   }
 }
 
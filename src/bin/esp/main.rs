@@ -0,0 +1,221 @@
+//! `esp` - check load shedding from the terminal.
+//!
+//! The token is read from the `ESKOMSEPUSH_API_KEY` env variable (or a .env file)
+//! and the base URL can be overridden with `ESKOMSEPUSH_BASE_URL`.
+//! Both can be set in the config file along with named areas (see [`eskom_se_push_api::config`]),
+//! where the base URL in the file takes precedence over `ESKOMSEPUSH_BASE_URL`.
+//! ```text
+//! esp status
+//! esp search "brooklyn"
+//! esp area tshwane-6-brooklyn --json
+//! esp watch home
+//! ```
+
+mod output;
+mod table;
+mod watch;
+
+use std::{path::PathBuf, process::ExitCode, time::Duration};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use eskom_se_push_api::{
+  area_info::TestMode,
+  config::Config,
+  errors::ConfigError,
+  feed_server::{FeedConfigBuilder, FeedServer},
+  try_get_token_from_env,
+  ureq_client::UreqClient,
+  EskomClient,
+};
+use serde::Serialize;
+
+use crate::output::Render;
+
+#[derive(Parser)]
+#[command(
+  name = "esp",
+  version,
+  about = "Check load shedding using the EskomSePush API"
+)]
+struct Cli {
+  /// Print the API response as JSON instead of a table
+  #[arg(long, global = true)]
+  json: bool,
+
+  /// The env variable the token is read from. Overrides the config [default: ESKOMSEPUSH_API_KEY]
+  #[arg(long, global = true, value_name = "VAR")]
+  token_env: Option<String>,
+
+  /// The config file [default: $ESKOMSEPUSH_CONFIG or eskom-se-push/config.toml in the config dir]
+  #[arg(long, global = true, value_name = "PATH")]
+  config: Option<PathBuf>,
+
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// The national load shedding status and any areas with their own status
+  Status,
+  /// Search for areas by name
+  Search {
+    /// The text to search for eg `brooklyn`
+    text: String,
+  },
+  /// The areas near a location
+  Nearby {
+    #[arg(allow_negative_numbers = true)]
+    lat: f32,
+    #[arg(allow_negative_numbers = true)]
+    long: f32,
+  },
+  /// The upcoming load shedding events for an area
+  Area {
+    /// The ID of the area eg `tshwane-6-brooklyn` or the name of an area in the config
+    id: String,
+    /// Return test data instead of the real schedule (doesn't use the quota)
+    #[arg(long, value_enum)]
+    test: Option<Test>,
+  },
+  /// The topics (user reports) near a location
+  Topics {
+    #[arg(allow_negative_numbers = true)]
+    lat: f32,
+    #[arg(allow_negative_numbers = true)]
+    long: f32,
+  },
+  /// How many API calls have been used today
+  Allowance,
+  /// A live view of an area with a countdown to the next outage, refreshed every `--interval`.
+  /// Each refresh uses 2 API calls
+  Watch {
+    /// The ID of the area eg `tshwane-6-brooklyn` or the name of an area in the config
+    id: String,
+    /// Minutes between refreshes. Doubles while the API returns Too Many Requests
+    #[arg(long, default_value_t = 120, value_parser = clap::value_parser!(u64).range(1..))]
+    interval: u64,
+    /// Use test data for the area instead of the real schedule
+    #[arg(long, value_enum)]
+    test: Option<Test>,
+  },
+  /// The areas saved in the config
+  Areas,
+  /// Serve iCalendar feeds of areas at `/calendar/<area-id>.ics` and the status at `/status.json`
+  /// for calendar apps to subscribe to
+  Serve {
+    /// The address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    addr: String,
+    /// Days of the schedule to project using the status, in addition to the events of the areas
+    #[arg(long, default_value_t = 0)]
+    days: usize,
+    /// The ID or name in the config of an area to serve. Can be repeated [default: the areas saved in the config]
+    #[arg(long = "area", value_name = "ID")]
+    areas: Vec<String>,
+  },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Test {
+  Current,
+  Future,
+}
+
+impl From<Test> for TestMode {
+  fn from(test: Test) -> Self {
+    match test {
+      Test::Current => TestMode::Current,
+      Test::Future => TestMode::Future,
+    }
+  }
+}
+
+type CliResult = Result<(), Box<dyn std::error::Error>>;
+
+fn print<T: Serialize + Render>(response: &T, json: bool) -> CliResult {
+  if json {
+    println!("{}", serde_json::to_string_pretty(response)?);
+  } else {
+    print!("{}", response.render());
+  }
+  Ok(())
+}
+
+fn client(config: &Config, token_env: Option<&str>) -> Result<UreqClient, ConfigError> {
+  let token = match token_env {
+    Some(token_env) => try_get_token_from_env(Some(token_env))?,
+    None => config.token()?,
+  };
+  Ok(UreqClient::try_new(token)?.with_base_url(config.base_url()))
+}
+
+fn run(cli: Cli) -> CliResult {
+  let config = match &cli.config {
+    Some(path) => Config::load_from(path)?,
+    None => Config::load()?,
+  };
+  // Listing the saved areas doesn't need a token
+  let api = || client(&config, cli.token_env.as_deref());
+  match cli.command {
+    Command::Status => print(&api()?.get_load_shedding_status()?, cli.json),
+    Command::Search { text } => print(&api()?.areas_search(&text)?, cli.json),
+    Command::Nearby { lat, long } => print(&api()?.areas_nearby(lat, long)?, cli.json),
+    Command::Area { id, test: None } => {
+      print(&api()?.get_area_info(config.area_id(&id))?, cli.json)
+    }
+    Command::Area {
+      id,
+      test: Some(test),
+    } => print(
+      &api()?.get_area_info_with_test(config.area_id(&id), test.into())?,
+      cli.json,
+    ),
+    Command::Topics { lat, long } => print(&api()?.topics_nearby(lat, long)?, cli.json),
+    Command::Allowance => print(&api()?.check_allowance()?, cli.json),
+    Command::Areas => print(&config.areas, cli.json),
+    Command::Serve { .. } if cli.json => Err("--json isn't supported by serve".into()),
+    Command::Serve { addr, days, areas } => {
+      let areas: Vec<String> = if areas.is_empty() {
+        config.areas.values().map(|area| area.id.clone()).collect()
+      } else {
+        areas
+          .iter()
+          .map(|area| config.area_id(area).to_owned())
+          .collect()
+      };
+      if areas.is_empty() {
+        return Err("There are no areas to serve, save one in the config or pass --area".into());
+      }
+      let feed = FeedConfigBuilder::default()
+        .areas(areas.clone())
+        .projected_days(days)
+        .build()?;
+      let server =
+        FeedServer::start(addr, api()?, feed).map_err(|e| e as Box<dyn std::error::Error>)?;
+      eprintln!("Serving feeds on {}", server.base_url());
+      for area in &areas {
+        eprintln!("  {}", server.calendar_url(area));
+      }
+      server.join();
+      Ok(())
+    }
+    Command::Watch { .. } if cli.json => Err("--json isn't supported by watch".into()),
+    Command::Watch { id, interval, test } => Ok(watch::run(
+      &api()?,
+      config.area_id(&id),
+      test.map(Into::into),
+      Duration::from_secs(interval * 60),
+    )?),
+  }
+}
+
+fn main() -> ExitCode {
+  match run(Cli::parse()) {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(e) => {
+      eprintln!("Error: {}", e);
+      ExitCode::FAILURE
+    }
+  }
+}
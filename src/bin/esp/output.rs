@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, TimeZone};
+use eskom_se_push_api::{
+  allowance::AllowanceCheck, area_info::AreaInfo, area_nearby::AreaNearby, area_search::AreaSearch,
+  config::SavedArea, schedule::sast, status::EskomStatus, topics_nearby::TopicsNearby,
+};
+
+use crate::table::Table;
+
+/// The longest topic body shown in the table
+const MAX_BODY_LEN: usize = 60;
+
+/// A response that can be printed for a person to read
+pub trait Render {
+  fn render(&self) -> String;
+}
+
+fn time<Tz: TimeZone>(time: &DateTime<Tz>) -> String
+where
+  Tz::Offset: std::fmt::Display,
+{
+  time.format("%a %d %b %H:%M").to_string()
+}
+
+fn or_none(table: Table, none: &str) -> String {
+  if table.is_empty() {
+    format!("{}\n", none)
+  } else {
+    table.to_string()
+  }
+}
+
+impl Render for EskomStatus {
+  fn render(&self) -> String {
+    let mut keys: Vec<&String> = self.status.keys().collect();
+    // The national status first then the rest alphabetically
+    keys.sort_by_key(|key| (key.as_str() != "eskom", key.as_str()));
+    let mut table = Table::new(["Area", "Stage", "Updated", "Next"]);
+    for key in keys {
+      let status = &self.status[key];
+      let next = status
+        .next_stages
+        .first()
+        .map(|next| {
+          format!(
+            "{} from {}",
+            next.get_stage(),
+            time(&next.stage_start_timestamp.with_timezone(&sast()))
+          )
+        })
+        .unwrap_or_default();
+      table.row([
+        status.name.clone(),
+        status.get_stage().to_string(),
+        status.stage_updated.clone(),
+        next,
+      ]);
+    }
+    or_none(table, "No statuses")
+  }
+}
+
+impl Render for AreaSearch {
+  fn render(&self) -> String {
+    let mut table = Table::new(["ID", "Name", "Region"]);
+    for area in &self.areas {
+      table.row([&area.id, &area.name, &area.region]);
+    }
+    or_none(table, "No areas found")
+  }
+}
+
+impl Render for AreaNearby {
+  fn render(&self) -> String {
+    let mut table = Table::new(["ID", "Name", "Region", "Count"]);
+    for area in &self.areas {
+      table.row([
+        area.id.clone(),
+        area.name.clone(),
+        area.region.clone(),
+        area.count.to_string(),
+      ]);
+    }
+    or_none(table, "No areas nearby")
+  }
+}
+
+impl Render for AreaInfo {
+  fn render(&self) -> String {
+    let mut table = Table::new(["Stage", "Start", "End"]);
+    for event in &self.events {
+      table.row([event.note.to_string(), time(&event.start), time(&event.end)]);
+    }
+    format!(
+      "{} ({})\n\n{}",
+      self.info.name,
+      self.info.region,
+      or_none(table, "No upcoming load shedding")
+    )
+  }
+}
+
+impl Render for TopicsNearby {
+  fn render(&self) -> String {
+    let mut table = Table::new(["Category", "Distance", "Followers", "Timestamp", "Body"]);
+    for topic in &self.topics {
+      let body = topic.body.replace('\n', " ");
+      let body = match body.char_indices().nth(MAX_BODY_LEN) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body,
+      };
+      table.row([
+        topic.category.clone(),
+        format!("{:.2}", topic.distance),
+        topic.followers.to_string(),
+        topic.timestamp.clone(),
+        body,
+      ]);
+    }
+    or_none(table, "No topics nearby")
+  }
+}
+
+impl Render for AllowanceCheck {
+  fn render(&self) -> String {
+    let allowance = &self.allowance;
+    let mut table = Table::new(["Used", "Limit", "Remaining", "Type"]);
+    table.row([
+      allowance.count.to_string(),
+      allowance.limit.to_string(),
+      (allowance.limit - allowance.count).to_string(),
+      allowance.type_field.clone(),
+    ]);
+    table.to_string()
+  }
+}
+
+impl Render for BTreeMap<String, SavedArea> {
+  fn render(&self) -> String {
+    let mut table = Table::new(["Name", "ID", "Area", "Region"]);
+    for (name, area) in self {
+      table.row([name, &area.id, &area.name, &area.region]);
+    }
+    or_none(table, "No areas saved in the config")
+  }
+}
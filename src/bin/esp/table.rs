@@ -0,0 +1,72 @@
+use std::fmt;
+
+/// A plain text table with the columns padded to the widest cell
+pub struct Table {
+  headers: Vec<String>,
+  rows: Vec<Vec<String>>,
+}
+
+impl Table {
+  pub fn new<I, S>(headers: I) -> Self
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    Table {
+      headers: headers.into_iter().map(Into::into).collect(),
+      rows: Vec::new(),
+    }
+  }
+
+  pub fn row<I, S>(&mut self, cells: I) -> &mut Self
+  where
+    I: IntoIterator<Item = S>,
+    S: ToString,
+  {
+    self
+      .rows
+      .push(cells.into_iter().map(|cell| cell.to_string()).collect());
+    self
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.rows.is_empty()
+  }
+
+  fn widths(&self) -> Vec<usize> {
+    let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+    for row in &self.rows {
+      for (i, cell) in row.iter().enumerate() {
+        let len = cell.chars().count();
+        match widths.get_mut(i) {
+          Some(width) => *width = (*width).max(len),
+          None => widths.push(len),
+        }
+      }
+    }
+    widths
+  }
+}
+
+fn write_row(f: &mut fmt::Formatter<'_>, cells: &[String], widths: &[usize]) -> fmt::Result {
+  let line = cells
+    .iter()
+    .zip(widths)
+    .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+    .collect::<Vec<_>>()
+    .join("  ");
+  writeln!(f, "{}", line.trim_end())
+}
+
+impl fmt::Display for Table {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let widths = self.widths();
+    write_row(f, &self.headers, &widths)?;
+    let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    write_row(f, &rule, &widths)?;
+    for row in &self.rows {
+      write_row(f, row, &widths)?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,281 @@
+use std::{
+  io::Write,
+  thread,
+  time::{Duration, Instant},
+};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use eskom_se_push_api::{
+  area_info::{AreaInfo, TestMode},
+  errors::{APIError, HttpError},
+  resolver::StatusResolver,
+  schedule::sast,
+  status::{LoadsheddingStatus, Stage},
+  ureq_client::UreqClient,
+  EskomClient,
+};
+
+use crate::table::Table;
+
+/// How often the view (and the countdown) is redrawn
+const REDRAW: Duration = Duration::from_secs(1);
+/// The longest the polling backs off to when the quota is exceeded
+const MAX_BACKOFF: Duration = Duration::from_secs(6 * 60 * 60);
+/// Clears the terminal and moves the cursor to the top left
+const CLEAR: &str = "\x1b[2J\x1b[H";
+
+/// The latest responses for the area being watched
+pub struct Snapshot {
+  pub info: AreaInfo,
+  pub status: Option<LoadsheddingStatus>,
+  pub fetched: DateTime<Utc>,
+}
+
+impl Snapshot {
+  fn fetch(api: &UreqClient, area_id: &str, test: Option<TestMode>) -> Result<Self, HttpError> {
+    let info = match test {
+      Some(test) => api.get_area_info_with_test(area_id, test)?,
+      None => api.get_area_info(area_id)?,
+    };
+    let status = api.get_load_shedding_status()?;
+    let status = StatusResolver::default()
+      .resolve_info(&status, &info.info)
+      .cloned();
+    Ok(Snapshot {
+      info,
+      status,
+      fetched: Utc::now(),
+    })
+  }
+
+  /// The stage of the area at the given time
+  fn stage_at(&self, now: &DateTime<FixedOffset>) -> Stage {
+    self
+      .status
+      .as_ref()
+      .map(|status| status.timeline().stage_at(now).clone())
+      .unwrap_or_default()
+  }
+}
+
+/// How long to wait before polling again. The delay doubles (up to [`MAX_BACKOFF`]) while the
+/// API returns [`APIError::TooManyRequests`] and goes back to the interval otherwise
+pub fn next_delay(delay: Duration, interval: Duration, error: Option<&HttpError>) -> Duration {
+  match error.and_then(HttpError::api_error) {
+    Some(APIError::TooManyRequests(_)) => (delay * 2).clamp(interval, MAX_BACKOFF.max(interval)),
+    _ => interval,
+  }
+}
+
+/// Formats a duration as eg `1h 05m 09s`
+fn countdown(duration: chrono::Duration) -> String {
+  let seconds = duration.num_seconds().max(0);
+  let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+  if hours > 0 {
+    format!("{}h {:02}m {:02}s", hours, minutes, seconds)
+  } else {
+    format!("{}m {:02}s", minutes, seconds)
+  }
+}
+
+/// Renders the view of the area at `now`
+pub fn render(
+  area_id: &str,
+  snapshot: Option<&Snapshot>,
+  error: Option<&str>,
+  now: DateTime<FixedOffset>,
+  next_poll: Duration,
+) -> String {
+  let mut view = String::new();
+  let Some(snapshot) = snapshot else {
+    view.push_str(&format!("Waiting for {}...\n", area_id));
+    if let Some(error) = error {
+      view.push_str(&format!("\nError: {}\n", error));
+    }
+    return view;
+  };
+  let info = &snapshot.info;
+  let stage = snapshot.stage_at(&now);
+  view.push_str(&format!(
+    "{} ({})\n{}\n\n",
+    info.info.name, info.info.region, stage
+  ));
+
+  let (state, change) = match info.current_event_at(&now) {
+    Some(event) => ("OFF", Some(("Back on in", event.end))),
+    None => (
+      "ON",
+      info
+        .next_event_at(&now)
+        .map(|event| ("Off in", event.start)),
+    ),
+  };
+  view.push_str(&format!("Power is {}\n", state));
+  match change {
+    Some((label, at)) => view.push_str(&format!(
+      "{} {} ({})\n",
+      label,
+      countdown(at.with_timezone(&Utc) - now.with_timezone(&Utc)),
+      at.format("%a %H:%M")
+    )),
+    None => view.push_str("No upcoming load shedding\n"),
+  }
+
+  view.push_str("\nToday\n");
+  let today = now.with_timezone(&sast()).date_naive();
+  let day = info
+    .schedule
+    .parse_days()
+    .ok()
+    .and_then(|days| days.into_iter().find(|day| day.date == today));
+  let mut table = Table::new(["Slot", "Status"]);
+  let local = now.with_timezone(&sast()).naive_local();
+  for range in day.as_ref().map_or(&[][..], |day| day.stage(&stage)) {
+    let (start, end) = range.on(today);
+    let marker = if start <= local && local < end {
+      "now"
+    } else if end <= local {
+      "done"
+    } else {
+      ""
+    };
+    table.row([range.to_string(), marker.to_owned()]);
+  }
+  if table.is_empty() {
+    view.push_str(&format!("No slots at {}\n", stage));
+  } else {
+    view.push_str(&table.to_string());
+  }
+
+  view.push_str(&format!(
+    "\nUpdated {} - next update in {}\n",
+    snapshot.fetched.with_timezone(&sast()).format("%H:%M:%S"),
+    countdown(chrono::Duration::from_std(next_poll).unwrap_or_default())
+  ));
+  if let Some(error) = error {
+    view.push_str(&format!("Error: {}\n", error));
+  }
+  view
+}
+
+/// Polls the area every `interval` and redraws the view until the process is stopped
+pub fn run(
+  api: &UreqClient,
+  area_id: &str,
+  test: Option<TestMode>,
+  interval: Duration,
+) -> Result<(), HttpError> {
+  let mut snapshot = None;
+  let mut error = None;
+  let mut delay = interval;
+  let mut next_poll = Instant::now();
+  loop {
+    if Instant::now() >= next_poll {
+      let result = Snapshot::fetch(api, area_id, test);
+      delay = next_delay(delay, interval, result.as_ref().err());
+      match result {
+        Ok(latest) => {
+          snapshot = Some(latest);
+          error = None;
+        }
+        // Retrying won't fix a bad token or area
+        Err(e) if !e.is_retryable() => return Err(e),
+        Err(e) => error = Some(e.to_string()),
+      }
+      next_poll = Instant::now() + delay;
+    }
+    let now = Utc::now().with_timezone(&sast());
+    let view = render(
+      area_id,
+      snapshot.as_ref(),
+      error.as_deref(),
+      now,
+      next_poll.saturating_duration_since(Instant::now()),
+    );
+    let mut stdout = std::io::stdout().lock();
+    let _ = write!(stdout, "{}{}", CLEAR, view);
+    let _ = stdout.flush();
+    drop(stdout);
+    thread::sleep(REDRAW);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use eskom_se_push_api::errors::ErrorContext;
+
+  use super::*;
+
+  fn snapshot() -> Snapshot {
+    let info: AreaInfo = serde_json::from_str(
+      r#"{
+        "events": [{"end": "2022-08-08T22:30:00+02:00", "note": "Stage 2", "start": "2022-08-08T20:00:00+02:00"}],
+        "info": {"name": "Brooklyn (6)", "region": "City of Tshwane"},
+        "schedule": {
+          "days": [{"date": "2022-08-08", "name": "Monday", "stages": [[], ["12:00-14:30", "20:00-22:30"]]}],
+          "source": "https://loadshedding.eskom.co.za/"
+        }
+      }"#,
+    )
+    .unwrap();
+    let status: LoadsheddingStatus = serde_json::from_str(
+      r#"{"name": "National", "next_stages": [], "stage": "2", "stage_updated": "2022-08-08T16:12:53+02:00"}"#,
+    )
+    .unwrap();
+    Snapshot {
+      info,
+      status: Some(status),
+      fetched: "2022-08-08T16:00:00Z".parse().unwrap(),
+    }
+  }
+
+  fn at(time: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(time).unwrap()
+  }
+
+  #[test]
+  fn counts_down_to_the_next_change() {
+    let snapshot = snapshot();
+    let view = render(
+      "tshwane-6-brooklyn",
+      Some(&snapshot),
+      None,
+      at("2022-08-08T19:14:30+02:00"),
+      Duration::from_secs(90),
+    );
+    assert!(view.contains("Stage 2\n"));
+    assert!(view.contains("Power is ON\nOff in 45m 30s (Mon 20:00)"));
+    assert!(view.contains("12:00-14:30  done"));
+    assert!(view.contains("Updated 18:00:00 - next update in 1m 30s"));
+
+    let view = render(
+      "tshwane-6-brooklyn",
+      Some(&snapshot),
+      None,
+      at("2022-08-08T20:30:00+02:00"),
+      Duration::ZERO,
+    );
+    assert!(view.contains("Power is OFF\nBack on in 2h 00m 00s (Mon 22:30)"));
+    assert!(view.contains("20:00-22:30  now"));
+  }
+
+  #[test]
+  fn backs_off_when_rate_limited() {
+    let interval = Duration::from_secs(60);
+    let limited = HttpError::APIError {
+      error: APIError::TooManyRequests(None),
+      context: ErrorContext::default(),
+    };
+    let delay = next_delay(interval, interval, Some(&limited));
+    assert_eq!(delay, Duration::from_secs(120));
+    assert_eq!(
+      next_delay(delay, interval, Some(&limited)),
+      Duration::from_secs(240)
+    );
+    assert_eq!(
+      next_delay(MAX_BACKOFF, interval, Some(&limited)),
+      MAX_BACKOFF
+    );
+    assert_eq!(next_delay(delay, interval, None), interval);
+  }
+}
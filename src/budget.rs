@@ -0,0 +1,439 @@
+//! A quota-aware request budgeter driven by the `api_allowance` endpoint.
+//!
+//! [`Budgeted`] wraps any client and periodically checks the allowance (which doesn't count towards the quota).
+//! Calls that would dip into the configured reserve are refused or deferred so polling loops can self-throttle.
+//! ```rust,no_run
+//! use eskom_se_push_api::{
+//!   budget::{BudgetConfigBuilder, Budgeted},
+//!   ureq_client::UreqClient,
+//!   EskomClient,
+//! };
+//!
+//! let config = BudgetConfigBuilder::default().reserve(5).build().unwrap();
+//! let api = Budgeted::new(UreqClient::new_with_env(None), config);
+//! match api.get_load_shedding_status() {
+//!   Ok(status) => println!("Stage {}", status.eskom().stage),
+//!   Err(e) => eprintln!("Error: {}", e),
+//! }
+//! if let Some(exhausted) = api.projected_exhaustion() {
+//!   println!("Quota runs out at {}", exhausted);
+//! }
+//! ```
+
+use std::{
+  collections::HashMap,
+  sync::Mutex,
+  time::{Duration, Instant},
+};
+
+#[cfg(any(feature = "async", doc))]
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derive_builder::Builder;
+
+#[cfg(any(feature = "async", doc))]
+use crate::EskomClientAsync;
+use crate::{
+  allowance::{Allowance, AllowanceCheck},
+  area_info::{AreaInfo, TestMode},
+  area_nearby::AreaNearby,
+  area_search::AreaSearch,
+  errors::{APIError, HttpError},
+  status::EskomStatus,
+  topics_nearby::TopicsNearby,
+  EskomClient,
+};
+
+/// The shortest wait between checks of the allowance while a call is deferred,
+/// so a zero `refresh_interval` doesn't call the API in a busy loop
+const MIN_DEFER_WAIT: Duration = Duration::from_secs(1);
+
+/// What happens to a call that would exceed the budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BudgetMode {
+  /// Returns [`HttpError::BudgetExceeded`] straight away
+  #[default]
+  Refuse,
+  /// Waits for the allowance to free up (eg the daily reset), checking it every `refresh_interval` (at least every second).
+  /// Returns [`HttpError::BudgetExceeded`] if it is still exceeded after `max_wait`.
+  Defer { max_wait: Duration },
+}
+
+/// The configuration for [`Budgeted`]
+#[derive(Debug, Clone, Builder)]
+#[builder(setter(into))]
+pub struct BudgetConfig {
+  /// The number of calls that are kept in reserve and never spent. Default is `0`
+  #[builder(default = "0")]
+  reserve: i64,
+  /// How often the allowance is checked. Calls made in between are tracked locally. Default is `10 minutes`
+  #[builder(default = "Duration::from_secs(10 * 60)")]
+  refresh_interval: Duration,
+  /// What happens to calls that would exceed the budget. Default is [`BudgetMode::Refuse`]
+  #[builder(default)]
+  mode: BudgetMode,
+}
+
+impl Default for BudgetConfig {
+  fn default() -> Self {
+    BudgetConfigBuilder::default().build().unwrap()
+  }
+}
+
+#[derive(Debug, Default)]
+struct BudgetState {
+  allowance: Option<Allowance>,
+  checked_at: Option<Instant>,
+  /// Calls made since the allowance was last checked
+  unconfirmed: i64,
+  /// Calls made per endpoint since the budgeter was created
+  spend: HashMap<&'static str, u64>,
+  /// The first allowance observed in the current quota period, used to project the spend rate
+  first_observed: Option<(DateTime<Utc>, i64)>,
+  /// The latest allowance observed in the current quota period
+  last_observed: Option<(DateTime<Utc>, i64)>,
+}
+
+impl BudgetState {
+  fn needs_refresh(&self, config: &BudgetConfig) -> bool {
+    !matches!(self.checked_at, Some(checked) if checked.elapsed() < config.refresh_interval)
+  }
+
+  fn update(&mut self, allowance: Allowance, now: DateTime<Utc>) {
+    let reset = !matches!(self.last_observed, Some((_, count)) if allowance.count >= count);
+    if reset {
+      self.first_observed = Some((now, allowance.count));
+    }
+    self.last_observed = Some((now, allowance.count));
+    self.allowance = Some(allowance);
+    self.checked_at = Some(Instant::now());
+    self.unconfirmed = 0;
+  }
+
+  fn remaining(&self) -> Option<i64> {
+    self
+      .allowance
+      .as_ref()
+      .map(|a| a.limit - a.count - self.unconfirmed)
+  }
+
+  fn try_spend(&mut self, endpoint: &'static str, config: &BudgetConfig) -> Result<(), HttpError> {
+    if let Some(remaining) = self.remaining() {
+      if remaining - 1 < config.reserve {
+        return Err(HttpError::BudgetExceeded {
+          remaining,
+          reserve: config.reserve,
+        });
+      }
+    }
+    self.unconfirmed += 1;
+    *self.spend.entry(endpoint).or_default() += 1;
+    Ok(())
+  }
+
+  fn exhausted(&mut self) {
+    if let Some(allowance) = self.allowance.as_mut() {
+      allowance.count = allowance.limit;
+      self.unconfirmed = 0;
+    }
+  }
+
+  fn projected_exhaustion(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let allowance = self.allowance.as_ref()?;
+    let (first_at, first_count) = self.first_observed?;
+    let count = allowance.count + self.unconfirmed;
+    if count >= allowance.limit {
+      return Some(now);
+    }
+    let spent = count - first_count;
+    let elapsed = (now - first_at).num_seconds();
+    if spent <= 0 || elapsed <= 0 {
+      return None;
+    }
+    let seconds_left = (allowance.limit - count) * elapsed / spent;
+    Some(now + chrono::Duration::seconds(seconds_left))
+  }
+}
+
+/// Wraps a client and keeps its API calls within the allowance.
+/// Implements [`EskomClient`] (and [`EskomClientAsync`]) when the wrapped client does.
+pub struct Budgeted<C> {
+  client: C,
+  config: BudgetConfig,
+  state: Mutex<BudgetState>,
+}
+
+impl<C> Budgeted<C> {
+  pub fn new(client: C, config: BudgetConfig) -> Self {
+    Budgeted {
+      client,
+      config,
+      state: Mutex::new(BudgetState::default()),
+    }
+  }
+
+  /// The wrapped client
+  pub fn client(&self) -> &C {
+    &self.client
+  }
+
+  /// The estimated number of calls remaining or `None` if the allowance hasn't been checked yet
+  pub fn remaining(&self) -> Option<i64> {
+    self.state.lock().unwrap().remaining()
+  }
+
+  /// The number of calls made per endpoint (eg `status` or `area`) through this budgeter
+  pub fn spend(&self) -> HashMap<String, u64> {
+    self
+      .state
+      .lock()
+      .unwrap()
+      .spend
+      .iter()
+      .map(|(endpoint, count)| (endpoint.to_string(), *count))
+      .collect()
+  }
+
+  /// When the quota is projected to run out based on the spend rate observed so far.
+  /// Returns `None` if there isn't enough information to make a projection.
+  pub fn projected_exhaustion(&self) -> Option<DateTime<Utc>> {
+    self.state.lock().unwrap().projected_exhaustion(Utc::now())
+  }
+
+  fn needs_refresh(&self) -> bool {
+    self.state.lock().unwrap().needs_refresh(&self.config)
+  }
+
+  fn update(&self, allowance: &AllowanceCheck) {
+    self
+      .state
+      .lock()
+      .unwrap()
+      .update(allowance.allowance.clone(), Utc::now());
+  }
+
+  /// Spends a call for the endpoint if it is within the budget
+  fn try_spend(&self, endpoint: &'static str, waited: Duration) -> Spend {
+    let mut state = self.state.lock().unwrap();
+    match (state.try_spend(endpoint, &self.config), self.config.mode) {
+      (Ok(()), _) => Spend::Allowed,
+      (Err(_), BudgetMode::Defer { max_wait }) if waited < max_wait => {
+        // Force the allowance to be checked again after waiting
+        state.checked_at = None;
+        let wait = self.config.refresh_interval.max(MIN_DEFER_WAIT);
+        Spend::Wait(wait.min(max_wait - waited))
+      }
+      (Err(e), _) => Spend::Refused(e),
+    }
+  }
+
+  fn record<T>(&self, result: &Result<T, HttpError>) {
+    if let Err(HttpError::APIError {
+      error: APIError::TooManyRequests(_),
+      ..
+    }) = result
+    {
+      self.state.lock().unwrap().exhausted();
+    }
+  }
+}
+
+enum Spend {
+  Allowed,
+  Wait(Duration),
+  Refused(HttpError),
+}
+
+impl<C: EskomClient> Budgeted<C> {
+  /// Checks the allowance and updates the budget
+  pub fn refresh(&self) -> Result<AllowanceCheck, HttpError> {
+    let allowance = self.client.check_allowance()?;
+    self.update(&allowance);
+    Ok(allowance)
+  }
+
+  fn budgeted<T>(
+    &self,
+    endpoint: &'static str,
+    call: impl FnOnce(&C) -> Result<T, HttpError>,
+  ) -> Result<T, HttpError> {
+    let mut waited = Duration::ZERO;
+    loop {
+      if self.needs_refresh() {
+        // The call is still allowed if the allowance can't be checked, it just isn't budgeted
+        let _ = self.refresh();
+      }
+      match self.try_spend(endpoint, waited) {
+        Spend::Allowed => break,
+        Spend::Wait(delay) => {
+          std::thread::sleep(delay);
+          waited += delay;
+        }
+        Spend::Refused(e) => return Err(e),
+      }
+    }
+    let result = call(&self.client);
+    self.record(&result);
+    result
+  }
+}
+
+impl<C: EskomClient> EskomClient for Budgeted<C> {
+  fn get_load_shedding_status(&self) -> Result<EskomStatus, HttpError> {
+    self.budgeted("status", |c| c.get_load_shedding_status())
+  }
+
+  fn get_area_info(&self, area_id: &str) -> Result<AreaInfo, HttpError> {
+    self.budgeted("area", |c| c.get_area_info(area_id))
+  }
+
+  /// Test mode doesn't use the quota so it isn't budgeted
+  fn get_area_info_with_test(&self, area_id: &str, test: TestMode) -> Result<AreaInfo, HttpError> {
+    self.client.get_area_info_with_test(area_id, test)
+  }
+
+  fn areas_nearby(&self, lat: f32, long: f32) -> Result<AreaNearby, HttpError> {
+    self.budgeted("areas_nearby", |c| c.areas_nearby(lat, long))
+  }
+
+  fn areas_search(&self, search_term: &str) -> Result<AreaSearch, HttpError> {
+    self.budgeted("areas_search", |c| c.areas_search(search_term))
+  }
+
+  fn topics_nearby(&self, lat: f32, long: f32) -> Result<TopicsNearby, HttpError> {
+    self.budgeted("topics_nearby", |c| c.topics_nearby(lat, long))
+  }
+
+  fn check_allowance(&self) -> Result<AllowanceCheck, HttpError> {
+    self.refresh()
+  }
+}
+
+#[cfg(any(feature = "async", doc))]
+impl<C: EskomClientAsync + Sync> Budgeted<C> {
+  /// Checks the allowance and updates the budget
+  pub async fn refresh_async(&self) -> Result<AllowanceCheck, HttpError> {
+    let allowance = self.client.check_allowance().await?;
+    self.update(&allowance);
+    Ok(allowance)
+  }
+
+  async fn reserve_async(&self, endpoint: &'static str) -> Result<(), HttpError> {
+    let mut waited = Duration::ZERO;
+    loop {
+      if self.needs_refresh() {
+        // The call is still allowed if the allowance can't be checked, it just isn't budgeted
+        let _ = self.refresh_async().await;
+      }
+      match self.try_spend(endpoint, waited) {
+        Spend::Allowed => return Ok(()),
+        Spend::Wait(delay) => {
+          tokio::time::sleep(delay).await;
+          waited += delay;
+        }
+        Spend::Refused(e) => return Err(e),
+      }
+    }
+  }
+}
+
+#[cfg(any(feature = "async", doc))]
+#[async_trait]
+impl<C: EskomClientAsync + Send + Sync> EskomClientAsync for Budgeted<C> {
+  async fn get_load_shedding_status(&self) -> Result<EskomStatus, HttpError> {
+    self.reserve_async("status").await?;
+    let result = self.client.get_load_shedding_status().await;
+    self.record(&result);
+    result
+  }
+
+  async fn get_area_info(&self, area_id: &str) -> Result<AreaInfo, HttpError> {
+    self.reserve_async("area").await?;
+    let result = self.client.get_area_info(area_id).await;
+    self.record(&result);
+    result
+  }
+
+  /// Test mode doesn't use the quota so it isn't budgeted
+  async fn get_area_info_with_test(
+    &self,
+    area_id: &str,
+    test: TestMode,
+  ) -> Result<AreaInfo, HttpError> {
+    self.client.get_area_info_with_test(area_id, test).await
+  }
+
+  async fn areas_nearby(&self, lat: f32, long: f32) -> Result<AreaNearby, HttpError> {
+    self.reserve_async("areas_nearby").await?;
+    let result = self.client.areas_nearby(lat, long).await;
+    self.record(&result);
+    result
+  }
+
+  async fn areas_search(&self, search_term: &str) -> Result<AreaSearch, HttpError> {
+    self.reserve_async("areas_search").await?;
+    let result = self.client.areas_search(search_term).await;
+    self.record(&result);
+    result
+  }
+
+  async fn topics_nearby(&self, lat: f32, long: f32) -> Result<TopicsNearby, HttpError> {
+    self.reserve_async("topics_nearby").await?;
+    let result = self.client.topics_nearby(lat, long).await;
+    self.record(&result);
+    result
+  }
+
+  async fn check_allowance(&self) -> Result<AllowanceCheck, HttpError> {
+    self.refresh_async().await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn allowance(count: i64) -> Allowance {
+    Allowance {
+      count,
+      limit: 50,
+      type_field: "daily".to_owned(),
+    }
+  }
+
+  #[test]
+  fn keeps_the_reserve() {
+    let config = BudgetConfigBuilder::default().reserve(8).build().unwrap();
+    let mut state = BudgetState::default();
+    state.update(allowance(40), Utc::now());
+    assert!(state.try_spend("status", &config).is_ok());
+    assert!(state.try_spend("area", &config).is_ok());
+    assert!(matches!(
+      state.try_spend("area", &config),
+      Err(HttpError::BudgetExceeded {
+        remaining: 8,
+        reserve: 8
+      })
+    ));
+    assert_eq!(state.spend["area"], 1);
+    assert_eq!(state.remaining(), Some(8));
+  }
+
+  #[test]
+  fn projects_exhaustion_from_spend_rate() {
+    let start = Utc::now();
+    let mut state = BudgetState::default();
+    state.update(allowance(10), start);
+    assert_eq!(state.projected_exhaustion(start), None);
+    // 10 calls an hour leaves 3 hours for the remaining 30 calls
+    let later = start + chrono::Duration::hours(1);
+    state.update(allowance(20), later);
+    assert_eq!(
+      state.projected_exhaustion(later),
+      Some(later + chrono::Duration::hours(3))
+    );
+    // The quota was reset
+    state.update(allowance(1), later);
+    assert_eq!(state.projected_exhaustion(later), None);
+  }
+}
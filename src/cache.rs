@@ -0,0 +1,282 @@
+//! Response caching so repeated calls don't use up the quota.
+//!
+//! Responses are cached per [`Endpoint::url()`](crate::Endpoint::url) with a time-to-live per endpoint.
+//! Only successful responses are cached and the `api_allowance` endpoint is never cached.
+//! See [`DiskCache`](crate::disk_cache::DiskCache) for a cache that survives restarts.
+//! ```rust,no_run
+//! use std::sync::Arc;
+//!
+//! use eskom_se_push_api::{cache::MemoryCache, ureq_client::UreqClient};
+//!
+//! let cache = Arc::new(MemoryCache::default());
+//! let api = UreqClient::new_with_env(None).with_cache(cache.clone());
+//! // Only the first call uses the quota
+//! let status = api.get_load_shedding_status();
+//! let status = api.get_load_shedding_status();
+//! // Forces the next call to fetch the latest status
+//! cache.invalidate_endpoint("status");
+//! ```
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+#[cfg(any(feature = "async", doc))]
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use derive_builder::Builder;
+use http::Method;
+
+#[cfg(any(feature = "async", doc))]
+use crate::transport::AsyncTransport;
+use crate::{errors::HttpError, transport::Transport};
+
+/// How long the responses of each endpoint are cached for.
+/// A TTL of zero disables caching for the endpoint.
+#[derive(Debug, Clone, Builder)]
+#[builder(setter(into))]
+pub struct CacheTtl {
+  /// TTL for the `status` endpoint. Default is `10 minutes`
+  #[builder(default = "Duration::from_secs(10 * 60)")]
+  status: Duration,
+  /// TTL for the `area` endpoint. Default is `1 hour`
+  #[builder(default = "Duration::from_secs(60 * 60)")]
+  area_info: Duration,
+  /// TTL for the `areas_search` endpoint. Default is `1 day`
+  #[builder(default = "Duration::from_secs(24 * 60 * 60)")]
+  areas_search: Duration,
+  /// TTL for the `areas_nearby` endpoint. Default is `1 day`
+  #[builder(default = "Duration::from_secs(24 * 60 * 60)")]
+  areas_nearby: Duration,
+  /// TTL for the `topics_nearby` endpoint. Default is `10 minutes`
+  #[builder(default = "Duration::from_secs(10 * 60)")]
+  topics_nearby: Duration,
+}
+
+impl Default for CacheTtl {
+  fn default() -> Self {
+    CacheTtlBuilder::default().build().unwrap()
+  }
+}
+
+impl CacheTtl {
+  /// The TTL for the endpoint of the URL or `None` if it shouldn't be cached
+  pub fn for_url(&self, url: &str) -> Option<Duration> {
+    let ttl = match endpoint_name(url) {
+      "status" => self.status,
+      "area" => self.area_info,
+      "areas_search" => self.areas_search,
+      "areas_nearby" => self.areas_nearby,
+      "topics_nearby" => self.topics_nearby,
+      _ => return None,
+    };
+    (!ttl.is_zero()).then_some(ttl)
+  }
+}
+
+/// The endpoint of the URL eg `status` or `area`
+pub(crate) fn endpoint_name(url: &str) -> &str {
+  let path = url.split(['?', '#']).next().unwrap_or_default();
+  path
+    .trim_end_matches('/')
+    .rsplit('/')
+    .next()
+    .unwrap_or_default()
+}
+
+/// A cached response body and when it was fetched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedResponse {
+  pub body: Bytes,
+  pub fetched_at: DateTime<Utc>,
+}
+
+impl CachedResponse {
+  pub fn new(body: Bytes) -> Self {
+    CachedResponse {
+      body,
+      fetched_at: Utc::now(),
+    }
+  }
+
+  /// Whether the response is younger than the `ttl`
+  pub fn is_fresh(&self, ttl: Duration) -> bool {
+    (Utc::now() - self.fetched_at)
+      .to_std()
+      .map_or(true, |age| age < ttl)
+  }
+}
+
+/// Storage for cached responses, keyed on the URL of the request
+pub trait ResponseCache: Send + Sync {
+  /// How long the responses of each endpoint are cached for
+  fn ttl(&self) -> &CacheTtl;
+
+  /// Returns the cached response for the URL even if it is no longer fresh
+  fn get(&self, url: &str) -> Option<CachedResponse>;
+
+  /// Stores the response for the URL
+  fn put(&self, url: &str, response: CachedResponse);
+
+  /// Removes the cached response for the URL
+  fn invalidate(&self, url: &str);
+
+  /// Removes all cached responses
+  fn clear(&self);
+
+  /// Whether the last good response should be returned (even if it is no longer fresh)
+  /// when the API is unreachable or returns a server error. Default is `false`
+  fn stale_if_error(&self) -> bool {
+    false
+  }
+}
+
+/// An in-memory [`ResponseCache`] that can be shared between clients
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+  ttl: CacheTtl,
+  stale_if_error: bool,
+  responses: Mutex<HashMap<String, CachedResponse>>,
+}
+
+impl MemoryCache {
+  pub fn new(ttl: CacheTtl) -> Self {
+    MemoryCache {
+      ttl,
+      stale_if_error: false,
+      responses: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Returns the last good response when the API is unreachable or returns a server error.
+  /// `Note`: Expired responses need to be kept for this so don't call [`MemoryCache::evict_expired`]
+  pub fn with_stale_if_error(mut self, stale_if_error: bool) -> Self {
+    self.stale_if_error = stale_if_error;
+    self
+  }
+
+  /// Removes the cached responses of an endpoint (eg `status` or `area`) for all queries
+  pub fn invalidate_endpoint(&self, endpoint: &str) {
+    self
+      .responses
+      .lock()
+      .unwrap()
+      .retain(|url, _| endpoint_name(url) != endpoint);
+  }
+
+  /// Removes the responses that are no longer fresh
+  pub fn evict_expired(&self) {
+    let ttl = &self.ttl;
+    self
+      .responses
+      .lock()
+      .unwrap()
+      .retain(|url, response| ttl.for_url(url).is_some_and(|ttl| response.is_fresh(ttl)));
+  }
+}
+
+impl ResponseCache for MemoryCache {
+  fn ttl(&self) -> &CacheTtl {
+    &self.ttl
+  }
+
+  fn get(&self, url: &str) -> Option<CachedResponse> {
+    self.responses.lock().unwrap().get(url).cloned()
+  }
+
+  fn put(&self, url: &str, response: CachedResponse) {
+    self
+      .responses
+      .lock()
+      .unwrap()
+      .insert(url.to_owned(), response);
+  }
+
+  fn invalidate(&self, url: &str) {
+    self.responses.lock().unwrap().remove(url);
+  }
+
+  fn clear(&self) {
+    self.responses.lock().unwrap().clear();
+  }
+
+  fn stale_if_error(&self) -> bool {
+    self.stale_if_error
+  }
+}
+
+/// A transport that serves fresh responses from the [`ResponseCache`] instead of the inner transport
+pub struct Cache<'a, T> {
+  transport: T,
+  cache: Option<&'a dyn ResponseCache>,
+}
+
+impl<'a, T> Cache<'a, T> {
+  /// `cache` is optional so the clients can use the same transport with or without a cache
+  pub fn new(transport: T, cache: Option<&'a dyn ResponseCache>) -> Self {
+    Cache { transport, cache }
+  }
+
+  /// Returns the cache and the key for the request if it can be cached
+  fn lookup(&self, request: &http::Request<Bytes>) -> Option<(&'a dyn ResponseCache, String)> {
+    let cache = self.cache?;
+    let url = request.uri().to_string();
+    (request.method() == Method::GET && cache.ttl().for_url(&url).is_some()).then_some((cache, url))
+  }
+
+  fn fresh(cache: &dyn ResponseCache, url: &str) -> Option<http::Response<Bytes>> {
+    let ttl = cache.ttl().for_url(url)?;
+    cache
+      .get(url)
+      .filter(|cached| cached.is_fresh(ttl))
+      .map(|cached| http::Response::new(cached.body))
+  }
+
+  /// Stores successful responses or falls back to a stale response if the API is unreachable
+  fn store(
+    cache: &dyn ResponseCache,
+    url: &str,
+    outcome: Result<http::Response<Bytes>, HttpError>,
+  ) -> Result<http::Response<Bytes>, HttpError> {
+    let unreachable = match &outcome {
+      Ok(response) if response.status().is_success() => {
+        cache.put(url, CachedResponse::new(response.body().clone()));
+        false
+      }
+      Ok(response) => response.status().is_server_error(),
+      Err(HttpError::Timeout { .. } | HttpError::NoInternet { .. }) => true,
+      Err(_) => false,
+    };
+    if unreachable && cache.stale_if_error() {
+      if let Some(stale) = cache.get(url) {
+        return Ok(http::Response::new(stale.body));
+      }
+    }
+    outcome
+  }
+}
+
+impl<T: Transport> Transport for Cache<'_, T> {
+  fn send(&self, request: http::Request<Bytes>) -> Result<http::Response<Bytes>, HttpError> {
+    let Some((cache, url)) = self.lookup(&request) else {
+      return self.transport.send(request);
+    };
+    if let Some(response) = Self::fresh(cache, &url) {
+      return Ok(response);
+    }
+    Self::store(cache, &url, self.transport.send(request))
+  }
+}
+
+#[cfg(any(feature = "async", doc))]
+#[async_trait]
+impl<T: AsyncTransport + Sync> AsyncTransport for Cache<'_, T> {
+  async fn send(&self, request: http::Request<Bytes>) -> Result<http::Response<Bytes>, HttpError> {
+    let Some((cache, url)) = self.lookup(&request) else {
+      return self.transport.send(request).await;
+    };
+    if let Some(response) = Self::fresh(cache, &url) {
+      return Ok(response);
+    }
+    Self::store(cache, &url, self.transport.send(request).await)
+  }
+}
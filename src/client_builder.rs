@@ -0,0 +1,122 @@
+//! A builder for the clients with timeouts, a proxy, a user agent and extra headers.
+//!
+//! The same builder is used for every backend, finish it with the `build_*` method of the backend
+//! or `wrap_*` to use an existing agent/client. The token is always added to the requests.
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use eskom_se_push_api::client_builder::ClientBuilder;
+//!
+//! let api = ClientBuilder::new("YOUR-TOKEN")
+//!   .connect_timeout(Duration::from_secs(5))
+//!   .read_timeout(Duration::from_secs(30))
+//!   .proxy("http://proxy.example.com:8080")
+//!   .user_agent("my-app/1.0")
+//!   .header("X-Request-Source", "dashboard")
+//!   .build_ureq()
+//!   .unwrap();
+//! let status = api.get_load_shedding_status();
+//! ```
+
+use std::time::Duration;
+
+use http::{
+  header::{HeaderName, USER_AGENT},
+  HeaderMap, HeaderValue,
+};
+
+use crate::{constants::TOKEN_KEY, errors::ConfigError, validate_token};
+
+/// Configures a client before it is built
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+  pub(crate) token: String,
+  pub(crate) connect_timeout: Option<Duration>,
+  pub(crate) read_timeout: Option<Duration>,
+  pub(crate) proxy: Option<String>,
+  user_agent: Option<String>,
+  headers: Vec<(String, String)>,
+}
+
+impl ClientBuilder {
+  /// `token` is the Eskom API token
+  pub fn new(token: impl Into<String>) -> Self {
+    ClientBuilder {
+      token: token.into(),
+      connect_timeout: None,
+      read_timeout: None,
+      proxy: None,
+      user_agent: None,
+      headers: Vec::new(),
+    }
+  }
+
+  /// The longest time to wait for a connection to the API
+  pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+    self.connect_timeout = Some(timeout);
+    self
+  }
+
+  /// The longest time to wait for a response from the API.
+  /// `Note`: The `reqwest` clients use this as the timeout of the whole request
+  pub fn read_timeout(mut self, timeout: Duration) -> Self {
+    self.read_timeout = Some(timeout);
+    self
+  }
+
+  /// Sends the requests through a proxy eg `http://proxy.example.com:8080`
+  pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+    self.proxy = Some(proxy.into());
+    self
+  }
+
+  /// The `User-Agent` header identifying your app
+  pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+    self.user_agent = Some(user_agent.into());
+    self
+  }
+
+  /// Adds a header to every request. The token header can't be overridden
+  pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+    self.headers.push((name.into(), value.into()));
+    self
+  }
+
+  /// Checks the token and the headers and returns the headers added to every request
+  pub(crate) fn default_headers(&self) -> Result<HeaderMap, ConfigError> {
+    validate_token(&self.token)?;
+    let mut headers = HeaderMap::new();
+    if let Some(user_agent) = &self.user_agent {
+      headers.insert(USER_AGENT, header_value(USER_AGENT.as_str(), user_agent)?);
+    }
+    for (name, value) in &self.headers {
+      let header_name =
+        HeaderName::from_bytes(name.as_bytes()).map_err(|e| ConfigError::InvalidHeaderName {
+          name: name.to_owned(),
+          source: e,
+        })?;
+      if header_name != TOKEN_KEY {
+        headers.append(header_name, header_value(name, value)?);
+      }
+    }
+    Ok(headers)
+  }
+
+  /// Checks the timeouts and proxy aren't set when wrapping an existing agent/client
+  /// since they can only be set when it is built
+  pub(crate) fn check_wrappable(&self) -> Result<(), ConfigError> {
+    if self.connect_timeout.is_some() || self.read_timeout.is_some() || self.proxy.is_some() {
+      return Err(ConfigError::ClientBuild(
+        "the timeouts and proxy need to be set on the wrapped agent/client".into(),
+      ));
+    }
+    Ok(())
+  }
+}
+
+fn header_value(name: &str, value: &str) -> Result<HeaderValue, ConfigError> {
+  HeaderValue::from_str(value).map_err(|source| ConfigError::InvalidHeader {
+    name: name.to_owned(),
+    source,
+  })
+}
@@ -0,0 +1,297 @@
+//! A TOML config file shared by the CLI and your own tools, holding the env variable of the token,
+//! the base URL and the areas you track under names of your choice.
+//!
+//! The file is read from `$ESKOMSEPUSH_CONFIG` if it is set or `eskom-se-push/config.toml`
+//! in the user's config directory eg `$XDG_CONFIG_HOME/eskom-se-push/config.toml` on Linux.
+//! The `base_url` in the file takes precedence over `ESKOMSEPUSH_BASE_URL`, which is only used
+//! (see [`get_base_url_from_env`]) when the file doesn't set one.
+//! ```toml
+//! token_env = "ESKOMSEPUSH_API_KEY"
+//! base_url = "https://developer.sepush.co.za/business/2.0"
+//!
+//! [areas.home]
+//! id = "tshwane-6-brooklyn"
+//! name = "Brooklyn (6)"
+//! region = "City of Tshwane"
+//! ```
+//! ```rust
+//! use eskom_se_push_api::{config::Config, Endpoint};
+//!
+//! let config: Config = r#"
+//! [areas.home]
+//! id = "tshwane-6-brooklyn"
+//! name = "Brooklyn (6)"
+//! region = "City of Tshwane"
+//! "#
+//! .parse()
+//! .unwrap();
+//! let url = config.area_info_url("home").unwrap().url().unwrap();
+//! assert!(url.as_str().ends_with("/area?id=tshwane-6-brooklyn"));
+//! ```
+
+use std::{
+  collections::BTreeMap,
+  fs,
+  path::{Path, PathBuf},
+  str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  area_info::{AreaInfoURL, AreaInfoURLBuilder},
+  area_nearby, area_search,
+  constants::CONFIG_ENV_KEY,
+  errors::ConfigError,
+  get_base_url_from_env, try_get_token_from_env,
+};
+
+/// The name of the directory used in the user's config directory
+const CONFIG_DIR_NAME: &str = "eskom-se-push";
+/// The name of the config file
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// The contents of the config file
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+  /// The env variable the token is read from.
+  /// Defaults to [`TOKEN_ENV_KEY`](crate::constants::TOKEN_ENV_KEY) if not set
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub token_env: Option<String>,
+  /// Overrides the base URL of the API, including `ESKOMSEPUSH_BASE_URL`.
+  /// Defaults to [`get_base_url_from_env`] if not set
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub base_url: Option<String>,
+  /// The saved areas by name eg `home` or `office`
+  pub areas: BTreeMap<String, SavedArea>,
+}
+
+/// An area saved in the config
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedArea {
+  /// ID of the area eg `tshwane-6-brooklyn`
+  pub id: String,
+  /// Name of the area eg `Brooklyn (6)`
+  pub name: String,
+  /// Region the area is in eg `City of Tshwane`
+  pub region: String,
+}
+
+impl From<&area_search::Area> for SavedArea {
+  fn from(area: &area_search::Area) -> Self {
+    SavedArea {
+      id: area.id.clone(),
+      name: area.name.clone(),
+      region: area.region.clone(),
+    }
+  }
+}
+
+impl From<&area_nearby::Area> for SavedArea {
+  fn from(area: &area_nearby::Area) -> Self {
+    SavedArea {
+      id: area.id.clone(),
+      name: area.name.clone(),
+      region: area.region.clone(),
+    }
+  }
+}
+
+impl FromStr for Config {
+  type Err = toml::de::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    toml::from_str(s)
+  }
+}
+
+impl Config {
+  /// The path of the config file. `$ESKOMSEPUSH_CONFIG` if it is set otherwise
+  /// `eskom-se-push/config.toml` in the user's config directory
+  pub fn default_path() -> Result<PathBuf, ConfigError> {
+    if let Some(path) = std::env::var_os(CONFIG_ENV_KEY).filter(|path| !path.is_empty()) {
+      return Ok(PathBuf::from(path));
+    }
+    dirs::config_dir()
+      .map(|dir| dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+      .ok_or(ConfigError::NoConfigDir)
+  }
+
+  /// Loads the config from [`Config::default_path`].
+  /// Returns an empty config if the file doesn't exist
+  pub fn load() -> Result<Self, ConfigError> {
+    Config::load_from(Config::default_path()?)
+  }
+
+  /// Loads the config from the file. Returns an empty config if the file doesn't exist
+  pub fn load_from(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+    let path = path.as_ref();
+    let contents = match fs::read_to_string(path) {
+      Ok(contents) => contents,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+      Err(source) => {
+        return Err(ConfigError::ConfigIo {
+          path: path.to_owned(),
+          source,
+        })
+      }
+    };
+    contents
+      .parse()
+      .map_err(|source| ConfigError::InvalidConfig {
+        path: path.to_owned(),
+        source,
+      })
+  }
+
+  /// Saves the config to [`Config::default_path`]
+  pub fn save(&self) -> Result<(), ConfigError> {
+    self.save_to(Config::default_path()?)
+  }
+
+  /// Saves the config to the file, creating the directory if it doesn't exist
+  pub fn save_to(&self, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+    let path = path.as_ref();
+    let contents = toml::to_string_pretty(self)?;
+    let io_error = |source| ConfigError::ConfigIo {
+      path: path.to_owned(),
+      source,
+    };
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+      fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    fs::write(path, contents).map_err(io_error)
+  }
+
+  /// The saved area with the name
+  pub fn area(&self, name: &str) -> Option<&SavedArea> {
+    self.areas.get(name)
+  }
+
+  /// Saves the area under the name, replacing the area previously saved under it
+  pub fn add_area(&mut self, name: impl Into<String>, area: impl Into<SavedArea>) -> &mut Self {
+    self.areas.insert(name.into(), area.into());
+    self
+  }
+
+  /// The ID of the saved area with the name, or `name_or_id` itself if there isn't one
+  pub fn area_id<'a>(&'a self, name_or_id: &'a str) -> &'a str {
+    self
+      .area(name_or_id)
+      .map_or(name_or_id, |area| area.id.as_str())
+  }
+
+  /// Gets the token from the env variable in the config
+  pub fn token(&self) -> Result<String, ConfigError> {
+    try_get_token_from_env(self.token_env.as_deref())
+  }
+
+  /// The base URL in the config or [`get_base_url_from_env`] if it isn't set
+  pub fn base_url(&self) -> String {
+    self
+      .base_url
+      .clone()
+      .unwrap_or_else(|| get_base_url_from_env(None))
+  }
+
+  /// Builds the [`AreaInfoURL`] of the saved area with the name, using [`Config::base_url`]
+  pub fn area_info_url(&self, name: &str) -> Result<AreaInfoURL, ConfigError> {
+    let area = self
+      .area(name)
+      .ok_or_else(|| ConfigError::UnknownArea(name.to_owned()))?;
+    // The area ID is the only required field and it is always set
+    Ok(
+      AreaInfoURLBuilder::default()
+        .area_id(area.id.clone())
+        .base_url(self.base_url())
+        .build()
+        .unwrap(),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Endpoint;
+
+  const CONFIG: &str = r#"
+token_env = "MY_ESP_TOKEN"
+base_url = "http://localhost:8080"
+
+[areas.home]
+id = "tshwane-6-brooklyn"
+name = "Brooklyn (6)"
+region = "City of Tshwane"
+
+[areas.office]
+id = "capetown-7-gardens"
+name = "Gardens (7)"
+region = "City of Cape Town"
+"#;
+
+  #[test]
+  fn resolves_saved_areas() {
+    let config: Config = CONFIG.parse().unwrap();
+    assert_eq!(config.token_env.as_deref(), Some("MY_ESP_TOKEN"));
+    assert_eq!(config.area("office").unwrap().region, "City of Cape Town");
+    assert_eq!(config.area_id("home"), "tshwane-6-brooklyn");
+    assert_eq!(config.area_id("eskde-10-fourways"), "eskde-10-fourways");
+    assert_eq!(
+      config
+        .area_info_url("home")
+        .unwrap()
+        .url()
+        .unwrap()
+        .as_str(),
+      "http://localhost:8080/area?id=tshwane-6-brooklyn"
+    );
+    assert!(matches!(
+      config.area_info_url("cottage"),
+      Err(ConfigError::UnknownArea(name)) if name == "cottage"
+    ));
+
+    // Without a base URL in the file the env variable is used
+    let config = Config {
+      base_url: None,
+      ..config
+    };
+    assert_eq!(config.base_url(), get_base_url_from_env(None));
+    assert_eq!(
+      config
+        .area_info_url("home")
+        .unwrap()
+        .url()
+        .unwrap()
+        .as_str(),
+      format!("{}/area?id=tshwane-6-brooklyn", get_base_url_from_env(None))
+    );
+  }
+
+  #[test]
+  fn round_trips_through_a_file() {
+    let dir = std::env::temp_dir().join(format!("esp-config-{}", fastrand::u64(..)));
+    let path = dir.join(CONFIG_FILE_NAME);
+    assert_eq!(Config::load_from(&path).unwrap(), Config::default());
+
+    let mut config = Config::default();
+    config.add_area(
+      "home",
+      &area_search::Area {
+        id: "tshwane-6-brooklyn".to_owned(),
+        name: "Brooklyn (6)".to_owned(),
+        region: "City of Tshwane".to_owned(),
+      },
+    );
+    config.save_to(&path).unwrap();
+    assert_eq!(Config::load_from(&path).unwrap(), config);
+
+    fs::write(&path, "areas = 1").unwrap();
+    assert!(matches!(
+      Config::load_from(&path),
+      Err(ConfigError::InvalidConfig { .. })
+    ));
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -1,2 +1,10 @@
 /// The Header key value for the Eskom-Se-Push token
 pub const TOKEN_KEY: &str = "token";
+/// The base URL of the Eskom-Se-Push API that all the endpoints are relative to
+pub const BASE_URL: &str = "https://developer.sepush.co.za/business/2.0";
+/// The default env variable used to override the base URL
+pub const BASE_URL_ENV_KEY: &str = "ESKOMSEPUSH_BASE_URL";
+/// The default env variable used for the Eskom-Se-Push token
+pub const TOKEN_ENV_KEY: &str = "ESKOMSEPUSH_API_KEY";
+/// The default env variable used to override the path of the config file
+pub const CONFIG_ENV_KEY: &str = "ESKOMSEPUSH_CONFIG";
@@ -0,0 +1,86 @@
+//! GPS coordinates used by the nearby endpoints.
+//! ```rust
+//! use eskom_se_push_api::{area_nearby::AreasNearbyURLBuilder, coordinates::Coordinates, Endpoint};
+//!
+//! let coordinates = Coordinates::new(-25.7479, 28.2293).unwrap();
+//! let url = AreasNearbyURLBuilder::default()
+//!   .coordinates(coordinates)
+//!   .build()
+//!   .unwrap()
+//!   .url()
+//!   .unwrap();
+//! assert_eq!(
+//!   url.as_str(),
+//!   "https://developer.sepush.co.za/business/2.0/areas_nearby?lat=-25.7479&long=28.2293"
+//! );
+//! assert!(Coordinates::new(-125.7, 28.2).is_err());
+//! ```
+
+use crate::errors::HttpError;
+
+/// A latitude and longitude that is validated to be a real location
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+  latitude: f32,
+  longitude: f32,
+}
+
+impl Coordinates {
+  /// Returns [`HttpError::InvalidCoordinates`] if the latitude isn't between -90 and 90
+  /// or the longitude isn't between -180 and 180
+  pub fn new(latitude: f32, longitude: f32) -> Result<Self, HttpError> {
+    if (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude) {
+      Ok(Coordinates {
+        latitude,
+        longitude,
+      })
+    } else {
+      Err(HttpError::InvalidCoordinates {
+        latitude,
+        longitude,
+      })
+    }
+  }
+
+  pub fn latitude(&self) -> f32 {
+    self.latitude
+  }
+
+  pub fn longitude(&self) -> f32 {
+    self.longitude
+  }
+
+  /// The `lat` and `long` query parameters
+  pub(crate) fn query_pairs(&self) -> Vec<(&'static str, String)> {
+    vec![
+      ("lat", self.latitude.to_string()),
+      ("long", self.longitude.to_string()),
+    ]
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn accepts_the_equator_and_prime_meridian() {
+    let coordinates = Coordinates::new(0.0, 0.0).unwrap();
+    assert_eq!(
+      coordinates.query_pairs(),
+      vec![("lat", "0".to_owned()), ("long", "0".to_owned())]
+    );
+    assert!(Coordinates::new(-90.0, 180.0).is_ok());
+  }
+
+  #[test]
+  fn rejects_out_of_range_values() {
+    assert!(matches!(
+      Coordinates::new(90.5, 28.2),
+      Err(HttpError::InvalidCoordinates { .. })
+    ));
+    assert!(Coordinates::new(-25.7, -180.1).is_err());
+    assert!(Coordinates::new(f32::NAN, 28.2).is_err());
+    assert!(Coordinates::new(-25.7, f32::INFINITY).is_err());
+  }
+}
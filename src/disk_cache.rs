@@ -0,0 +1,155 @@
+//! A persistent on-disk response cache that survives restarts.
+//!
+//! Each response is stored as a JSON file holding the URL, the raw body and the time it was fetched.
+//! Useful for short-lived CLI tools and cron jobs which would otherwise use the quota on every run.
+//! ```rust,no_run
+//! use std::sync::Arc;
+//!
+//! use eskom_se_push_api::{cache::CacheTtl, disk_cache::DiskCache, ureq_client::UreqClient};
+//!
+//! let cache = DiskCache::in_user_cache_dir(CacheTtl::default())
+//!   .unwrap()
+//!   .with_stale_if_error(true);
+//! let api = UreqClient::new_with_env(None).with_cache(Arc::new(cache));
+//! // Uses the cached status from a previous run if it is still fresh
+//! // or the last good status if the API is unreachable
+//! let status = api.get_load_shedding_status();
+//! ```
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{endpoint_name, CacheTtl, CachedResponse, ResponseCache};
+
+/// The name of the directory used in the user's cache directory
+const CACHE_DIR_NAME: &str = "eskom-se-push";
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+  url: String,
+  fetched_at: DateTime<Utc>,
+  body: String,
+}
+
+/// A [`ResponseCache`] that stores the responses as JSON files in a directory
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+  dir: PathBuf,
+  ttl: CacheTtl,
+  stale_if_error: bool,
+}
+
+impl DiskCache {
+  /// Stores the responses in `dir`, it is created if it doesn't exist
+  pub fn new(dir: impl Into<PathBuf>, ttl: CacheTtl) -> std::io::Result<Self> {
+    let dir = dir.into();
+    fs::create_dir_all(&dir)?;
+    Ok(DiskCache {
+      dir,
+      ttl,
+      stale_if_error: false,
+    })
+  }
+
+  /// Stores the responses in the user's cache directory eg `$XDG_CACHE_HOME/eskom-se-push` on Linux
+  pub fn in_user_cache_dir(ttl: CacheTtl) -> std::io::Result<Self> {
+    let dir = dirs::cache_dir().ok_or_else(|| {
+      std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        "Unable to find the user's cache directory",
+      )
+    })?;
+    DiskCache::new(dir.join(CACHE_DIR_NAME), ttl)
+  }
+
+  /// Returns the last good response when the API is unreachable or returns a server error
+  pub fn with_stale_if_error(mut self, stale_if_error: bool) -> Self {
+    self.stale_if_error = stale_if_error;
+    self
+  }
+
+  /// The directory the responses are stored in
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  /// The file the response for the URL is stored in
+  fn path(&self, url: &str) -> PathBuf {
+    self
+      .dir
+      .join(format!("{}-{:016x}.json", endpoint_name(url), fnv1a(url)))
+  }
+
+  fn read(&self, path: &Path) -> Option<CacheFile> {
+    let contents = fs::read(path).ok()?;
+    serde_json::from_slice(&contents).ok()
+  }
+}
+
+impl ResponseCache for DiskCache {
+  fn ttl(&self) -> &CacheTtl {
+    &self.ttl
+  }
+
+  fn get(&self, url: &str) -> Option<CachedResponse> {
+    self
+      .read(&self.path(url))
+      .filter(|file| file.url == url)
+      .map(|file| CachedResponse {
+        body: Bytes::from(file.body),
+        fetched_at: file.fetched_at,
+      })
+  }
+
+  fn put(&self, url: &str, response: CachedResponse) {
+    let Ok(body) = String::from_utf8(response.body.to_vec()) else {
+      return;
+    };
+    let file = CacheFile {
+      url: url.to_owned(),
+      fetched_at: response.fetched_at,
+      body,
+    };
+    let Ok(contents) = serde_json::to_vec(&file) else {
+      return;
+    };
+    // Write to a temporary file first so a crash doesn't leave a half written response behind
+    let path = self.path(url);
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+    if fs::write(&tmp, contents).is_ok() && fs::rename(&tmp, &path).is_err() {
+      let _ = fs::remove_file(&tmp);
+    }
+  }
+
+  fn invalidate(&self, url: &str) {
+    let _ = fs::remove_file(self.path(url));
+  }
+
+  fn clear(&self) {
+    if let Ok(entries) = fs::read_dir(&self.dir) {
+      for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+          let _ = fs::remove_file(path);
+        }
+      }
+    }
+  }
+
+  fn stale_if_error(&self) -> bool {
+    self.stale_if_error
+  }
+}
+
+/// A stable hash of the URL for the file name. `DefaultHasher` isn't guaranteed to be stable between Rust releases.
+fn fnv1a(value: &str) -> u64 {
+  value.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+    (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+  })
+}
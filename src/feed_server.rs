@@ -0,0 +1,310 @@
+//! A small HTTP server that publishes the outages of areas as iCalendar feeds calendar apps can subscribe to.
+//!
+//! Serves `/calendar/<area-id>.ics` (see [`Calendar`]) for the allowed `areas` and `/status.json`
+//! (the [`EskomStatus`]). The responses of the API are cached by the server so any number of subscribers
+//! cost at most one call per area every `area_ttl` plus one call every `status_ttl`. A failed call is
+//! cached for `error_ttl` and the last good response is served while the API is unavailable.
+//! ```rust,no_run
+//! use eskom_se_push_api::{
+//!   feed_server::{FeedConfigBuilder, FeedServer},
+//!   ureq_client::UreqClient,
+//! };
+//!
+//! let config = FeedConfigBuilder::default()
+//!   .areas(vec!["tshwane-6-brooklyn".to_owned()])
+//!   .projected_days(7usize)
+//!   .build()
+//!   .unwrap();
+//! let server = FeedServer::start("127.0.0.1:8080", UreqClient::new_with_env(None), config).unwrap();
+//! // Subscribe to http://127.0.0.1:8080/calendar/tshwane-6-brooklyn.ics
+//! println!("{}", server.calendar_url("tshwane-6-brooklyn"));
+//! server.join();
+//! ```
+//!
+//! # Optional
+//! Requires the `feed-server` feature to be enabled
+
+use std::{
+  collections::HashMap,
+  net::{SocketAddr, ToSocketAddrs},
+  sync::{Arc, Mutex},
+  thread::JoinHandle,
+  time::{Duration, Instant},
+};
+
+use derive_builder::Builder;
+
+use crate::{
+  area_info::AreaInfo,
+  errors::{BoxError, HttpError},
+  ical::Calendar,
+  predict::predict,
+  resolver::StatusResolver,
+  status::EskomStatus,
+  EskomClient,
+};
+
+/// How the [`FeedServer`] caches and what it serves
+#[derive(Debug, Clone, Builder)]
+#[builder(setter(into))]
+pub struct FeedConfig {
+  /// The IDs of the areas that can be subscribed to, other areas return `404`.
+  /// Required so the number of areas fetched from the API is bounded
+  areas: Vec<String>,
+  /// How long the status is cached for. Default is `10 minutes`
+  #[builder(default = "Duration::from_secs(10 * 60)")]
+  status_ttl: Duration,
+  /// How long the info of each area is cached for. Default is `1 hour`
+  #[builder(default = "Duration::from_secs(60 * 60)")]
+  area_ttl: Duration,
+  /// How long a failed call is cached for before the API is called again. Default is `1 minute`
+  #[builder(default = "Duration::from_secs(60)")]
+  error_ttl: Duration,
+  /// The number of days of the schedule that are projected using the status, in addition to the events of the area.
+  /// Default is `0` which only includes the events. `NOTE` Projecting also uses the status endpoint
+  #[builder(default)]
+  projected_days: usize,
+}
+
+/// The result of the last call for a response
+struct CacheEntry<T> {
+  /// The last good response
+  value: Option<T>,
+  /// The error of the last call if it failed
+  error: Option<String>,
+  checked: Instant,
+}
+
+impl<T: Clone> CacheEntry<T> {
+  /// The last good response, even if the last call failed
+  fn result(&self) -> Result<T, String> {
+    match (&self.value, &self.error) {
+      (Some(value), _) => Ok(value.clone()),
+      (None, error) => Err(error.clone().unwrap_or_default()),
+    }
+  }
+}
+
+/// A cached response. The lock is only held to read or update the entry, not while the API is called
+struct Cached<T> {
+  entry: Mutex<Option<CacheEntry<T>>>,
+}
+
+impl<T: Clone> Cached<T> {
+  fn new() -> Self {
+    Cached {
+      entry: Mutex::new(None),
+    }
+  }
+
+  /// Returns the cached response if it was checked within the TTL (or `error_ttl` if the call failed),
+  /// otherwise calls the API. The last good response is returned if the call fails
+  fn get(
+    &self,
+    ttl: Duration,
+    error_ttl: Duration,
+    fetch: impl FnOnce() -> Result<T, HttpError>,
+  ) -> Result<T, String> {
+    if let Some(entry) = self.entry.lock().unwrap().as_ref() {
+      let ttl = if entry.error.is_some() {
+        error_ttl
+      } else {
+        ttl
+      };
+      if entry.checked.elapsed() < ttl {
+        return entry.result();
+      }
+    }
+    let latest = fetch();
+    let mut entry = self.entry.lock().unwrap();
+    let value = entry.take().and_then(|entry| entry.value);
+    let updated = match latest {
+      Ok(latest) => CacheEntry {
+        value: Some(latest),
+        error: None,
+        checked: Instant::now(),
+      },
+      Err(e) => CacheEntry {
+        value,
+        error: Some(e.to_string()),
+        checked: Instant::now(),
+      },
+    };
+    entry.insert(updated).result()
+  }
+}
+
+struct FeedState<C> {
+  client: C,
+  config: FeedConfig,
+  status: Cached<EskomStatus>,
+  /// An entry for each allowed area so no lock is needed to look them up
+  areas: HashMap<String, Cached<AreaInfo>>,
+}
+
+impl<C: EskomClient> FeedState<C> {
+  fn new(client: C, config: FeedConfig) -> Self {
+    let areas = config
+      .areas
+      .iter()
+      .map(|id| (id.clone(), Cached::new()))
+      .collect();
+    FeedState {
+      client,
+      config,
+      status: Cached::new(),
+      areas,
+    }
+  }
+
+  fn status(&self) -> Result<EskomStatus, String> {
+    self
+      .status
+      .get(self.config.status_ttl, self.config.error_ttl, || {
+        self.client.get_load_shedding_status()
+      })
+  }
+
+  fn calendar(&self, area_id: &str, cached: &Cached<AreaInfo>) -> Result<Calendar, String> {
+    let info = cached.get(self.config.area_ttl, self.config.error_ttl, || {
+      self.client.get_area_info(area_id)
+    })?;
+    if self.config.projected_days > 0 {
+      // Falls back to only the events if the status isn't available
+      let outages = self.status().ok().and_then(|status| {
+        let status = StatusResolver::default().resolve_info(&status, &info.info)?;
+        predict(status, &info, self.config.projected_days).ok()
+      });
+      if let Some(outages) = outages {
+        return Ok(Calendar::new(area_id, &info.info.name, outages));
+      }
+    }
+    Ok(Calendar::from_events(area_id, &info))
+  }
+
+  fn handle(&self, method: &tiny_http::Method, url: &str) -> Reply {
+    if *method != tiny_http::Method::Get && *method != tiny_http::Method::Head {
+      return Reply::text(405, "Method not allowed");
+    }
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    if path == "/status.json" {
+      return match self
+        .status()
+        .and_then(|s| serde_json::to_string(&s).map_err(|e| e.to_string()))
+      {
+        Ok(body) => Reply::new(200, "application/json", body),
+        Err(e) => Reply::text(502, &e),
+      };
+    }
+    let area_id = path
+      .strip_prefix("/calendar/")
+      .and_then(|file| file.strip_suffix(".ics"));
+    let Some(area_id) = area_id else {
+      return Reply::text(404, "Not found");
+    };
+    let Some(cached) = self.areas.get(area_id) else {
+      return Reply::text(404, "Unknown area");
+    };
+    match self.calendar(area_id, cached) {
+      Ok(calendar) => Reply::new(200, "text/calendar; charset=utf-8", calendar.to_string()),
+      Err(e) => Reply::text(502, &e),
+    }
+  }
+}
+
+struct Reply {
+  status: u16,
+  content_type: &'static str,
+  body: String,
+}
+
+impl Reply {
+  fn new(status: u16, content_type: &'static str, body: String) -> Self {
+    Reply {
+      status,
+      content_type,
+      body,
+    }
+  }
+
+  fn text(status: u16, body: &str) -> Self {
+    Reply::new(status, "text/plain; charset=utf-8", body.to_owned())
+  }
+}
+
+/// The feed server running on a background thread.
+/// The server is shut down when it is dropped.
+pub struct FeedServer {
+  server: Arc<tiny_http::Server>,
+  addr: SocketAddr,
+  handle: Option<JoinHandle<()>>,
+}
+
+impl FeedServer {
+  /// Starts the server on the address eg `127.0.0.1:8080` (use port `0` for a random port).
+  /// The client is used to call the API, see [`EskomClient`]
+  pub fn start<C>(addr: impl ToSocketAddrs, client: C, config: FeedConfig) -> Result<Self, BoxError>
+  where
+    C: EskomClient + Send + 'static,
+  {
+    let server = Arc::new(tiny_http::Server::http(addr)?);
+    let addr = server
+      .server_addr()
+      .to_ip()
+      .ok_or("The feed server is not listening on an IP address")?;
+    let state = FeedState::new(client, config);
+
+    let handle = {
+      let server = server.clone();
+      std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+          let reply = state.handle(request.method(), request.url());
+          let content_type =
+            tiny_http::Header::from_bytes(&b"Content-Type"[..], reply.content_type.as_bytes())
+              .unwrap();
+          let response = tiny_http::Response::from_string(reply.body)
+            .with_status_code(reply.status)
+            .with_header(content_type);
+          let _ = request.respond(response);
+        }
+      })
+    };
+
+    Ok(FeedServer {
+      server,
+      addr,
+      handle: Some(handle),
+    })
+  }
+
+  /// The address the server is listening on
+  pub fn addr(&self) -> SocketAddr {
+    self.addr
+  }
+
+  /// The base URL of the server eg `http://127.0.0.1:8080`
+  pub fn base_url(&self) -> String {
+    format!("http://{}", self.addr)
+  }
+
+  /// The URL of the calendar of the area to subscribe to
+  pub fn calendar_url(&self, area_id: &str) -> String {
+    format!("{}/calendar/{}.ics", self.base_url(), area_id)
+  }
+
+  /// Blocks until the server stops
+  pub fn join(mut self) {
+    if let Some(handle) = self.handle.take() {
+      let _ = handle.join();
+    }
+  }
+}
+
+impl Drop for FeedServer {
+  fn drop(&mut self) {
+    self.server.unblock();
+    if let Some(handle) = self.handle.take() {
+      let _ = handle.join();
+    }
+  }
+}
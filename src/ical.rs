@@ -0,0 +1,275 @@
+//! Exports the outages of an area as an [RFC 5545](https://datatracker.ietf.org/doc/html/rfc5545) iCalendar
+//! so they can be imported into (or subscribed to from) Outlook, Google Calendar etc.
+//!
+//! The UID of each event is derived from the area ID and the start of the outage so importing the
+//! calendar again updates the events instead of duplicating them.
+//! ```rust,no_run
+//! use eskom_se_push_api::{ical::Calendar, status::Stage, ureq_client::UreqClient};
+//!
+//! let api = UreqClient::new_with_env(None);
+//! let info = api.get_area_info("tshwane-6-brooklyn").unwrap();
+//! // The events of the area plus the rest of the week's schedule at Stage 2
+//! let calendar = Calendar::from_projection("tshwane-6-brooklyn", &info, &Stage::Stage2, 7).unwrap();
+//! std::fs::write("brooklyn.ics", calendar.to_string()).unwrap();
+//! ```
+
+use std::fmt;
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::{
+  area_info::AreaInfo,
+  errors::ScheduleError,
+  predict::{combine, PredictedOutage, Source},
+  status::Stage,
+};
+
+/// The product identifier of the calendars
+const PRODID: &str = "-//eskom-se-push-api//Load Shedding//EN";
+/// The domain part of the event UIDs
+const UID_DOMAIN: &str = "eskom-se-push-api";
+/// The longest a content line can be in octets, excluding the line break
+const MAX_LINE_LEN: usize = 75;
+
+/// An iCalendar of the outages of an area. [`Display`](fmt::Display) writes it out as a `VCALENDAR`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calendar {
+  area_id: String,
+  name: String,
+  outages: Vec<PredictedOutage>,
+  generated_at: DateTime<Utc>,
+}
+
+impl Calendar {
+  /// A calendar of the outages eg from [`predict`](crate::predict::predict)
+  pub fn new(
+    area_id: impl Into<String>,
+    name: impl Into<String>,
+    outages: Vec<PredictedOutage>,
+  ) -> Self {
+    Calendar {
+      area_id: area_id.into(),
+      name: name.into(),
+      outages,
+      generated_at: Utc::now(),
+    }
+  }
+
+  /// A calendar of the events of the area
+  pub fn from_events(area_id: impl Into<String>, info: &AreaInfo) -> Self {
+    Calendar::new(area_id, &info.info.name, combine(&info.events, Vec::new()))
+  }
+
+  /// A calendar of the events of the area plus the outages projected from the first `days` days of
+  /// its schedule at the stage, for the times not covered by an event
+  pub fn from_projection(
+    area_id: impl Into<String>,
+    info: &AreaInfo,
+    stage: &Stage,
+    days: usize,
+  ) -> Result<Self, ScheduleError> {
+    let projected = info.schedule.project_stage(stage, days)?;
+    Ok(Calendar::new(
+      area_id,
+      &info.info.name,
+      combine(&info.events, projected),
+    ))
+  }
+
+  /// Sets the time the calendar was generated (the `DTSTAMP` of the events). Default is now
+  pub fn with_generated_at<Tz: TimeZone>(mut self, generated_at: &DateTime<Tz>) -> Self {
+    self.generated_at = generated_at.with_timezone(&Utc);
+    self
+  }
+
+  /// The outages in the calendar
+  pub fn outages(&self) -> &[PredictedOutage] {
+    &self.outages
+  }
+
+  /// The UID of the event for the outage eg `tshwane-6-brooklyn-20220808T180000Z@eskom-se-push-api`
+  pub fn uid(&self, outage: &PredictedOutage) -> String {
+    format!(
+      "{}-{}@{}",
+      self.area_id,
+      timestamp(&outage.start),
+      UID_DOMAIN
+    )
+  }
+}
+
+/// Formats the time in UTC eg `20220808T180000Z`
+fn timestamp<Tz: TimeZone>(time: &DateTime<Tz>) -> String {
+  time
+    .with_timezone(&Utc)
+    .format("%Y%m%dT%H%M%SZ")
+    .to_string()
+}
+
+/// Escapes the characters that have a special meaning in a `TEXT` value
+fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '\\' | ';' | ',' => {
+        escaped.push('\\');
+        escaped.push(c);
+      }
+      '\n' => escaped.push_str("\\n"),
+      '\r' => {}
+      _ => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+/// Writes the content line, folding it onto continuation lines if it is longer than 75 octets
+fn write_line(f: &mut fmt::Formatter<'_>, line: &str) -> fmt::Result {
+  let mut len = 0;
+  for c in line.chars() {
+    // Continuation lines start with a space which counts towards their length
+    if len + c.len_utf8() > MAX_LINE_LEN {
+      f.write_str("\r\n ")?;
+      len = 1;
+    }
+    write!(f, "{}", c)?;
+    len += c.len_utf8();
+  }
+  f.write_str("\r\n")
+}
+
+impl fmt::Display for Calendar {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write_line(f, "BEGIN:VCALENDAR")?;
+    write_line(f, "VERSION:2.0")?;
+    write_line(f, &format!("PRODID:{}", PRODID))?;
+    write_line(f, "CALSCALE:GREGORIAN")?;
+    write_line(f, "METHOD:PUBLISH")?;
+    write_line(
+      f,
+      &format!(
+        "X-WR-CALNAME:{}",
+        escape(&format!("Load shedding - {}", self.name))
+      ),
+    )?;
+    let generated_at = timestamp(&self.generated_at);
+    for outage in &self.outages {
+      write_line(f, "BEGIN:VEVENT")?;
+      write_line(f, &format!("UID:{}", self.uid(outage)))?;
+      write_line(f, &format!("DTSTAMP:{}", generated_at))?;
+      write_line(f, &format!("DTSTART:{}", timestamp(&outage.start)))?;
+      write_line(f, &format!("DTEND:{}", timestamp(&outage.end)))?;
+      write_line(f, &format!("SUMMARY:{}", escape(&outage.note)))?;
+      write_line(f, &format!("LOCATION:{}", escape(&self.name)))?;
+      match outage.source {
+        Source::Event => write_line(f, "STATUS:CONFIRMED")?,
+        Source::Projected => {
+          write_line(f, "STATUS:TENTATIVE")?;
+          write_line(f, "DESCRIPTION:Projected from the schedule of the area")?;
+        }
+      }
+      write_line(f, "TRANSP:OPAQUE")?;
+      write_line(f, "END:VEVENT")?;
+    }
+    write_line(f, "END:VCALENDAR")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::area_info::{Day, Event, Info, Schedule};
+
+  fn time(time: &str) -> DateTime<chrono::FixedOffset> {
+    DateTime::parse_from_rfc3339(time).unwrap()
+  }
+
+  fn info() -> AreaInfo {
+    AreaInfo {
+      events: vec![Event::new(
+        time("2022-08-08T20:00:00+02:00"),
+        time("2022-08-08T22:30:00+02:00"),
+        "Stage 2 (TESTING: current)",
+      )],
+      info: Info {
+        name: "Brooklyn (6)".to_owned(),
+        region: "City of Tshwane".to_owned(),
+      },
+      schedule: Schedule {
+        days: vec![Day {
+          date: "2022-08-08".to_owned(),
+          name: "Monday".to_owned(),
+          stages: vec![
+            vec!["04:00-06:30".to_owned()],
+            vec!["04:00-06:30".to_owned(), "20:00-22:30".to_owned()],
+          ],
+        }],
+        source: "https://loadshedding.eskom.co.za/".to_owned(),
+      },
+    }
+  }
+
+  #[test]
+  fn writes_an_event_per_outage() {
+    let calendar = Calendar::from_events("tshwane-6-brooklyn", &info())
+      .with_generated_at(&time("2022-08-08T12:00:00+02:00"));
+    assert_eq!(
+      calendar.to_string(),
+      [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//eskom-se-push-api//Load Shedding//EN",
+        "CALSCALE:GREGORIAN",
+        "METHOD:PUBLISH",
+        "X-WR-CALNAME:Load shedding - Brooklyn (6)",
+        "BEGIN:VEVENT",
+        "UID:tshwane-6-brooklyn-20220808T180000Z@eskom-se-push-api",
+        "DTSTAMP:20220808T100000Z",
+        "DTSTART:20220808T180000Z",
+        "DTEND:20220808T203000Z",
+        "SUMMARY:Stage 2 (TESTING: current)",
+        "LOCATION:Brooklyn (6)",
+        "STATUS:CONFIRMED",
+        "TRANSP:OPAQUE",
+        "END:VEVENT",
+        "END:VCALENDAR",
+        "",
+      ]
+      .join("\r\n")
+    );
+  }
+
+  #[test]
+  fn projects_the_schedule_with_stable_uids() {
+    let calendar =
+      Calendar::from_projection("tshwane-6-brooklyn", &info(), &Stage::Stage2, 1).unwrap();
+    let outages = calendar.outages();
+    assert_eq!(outages.len(), 2);
+    assert_eq!(outages[0].source, Source::Projected);
+    assert_eq!(outages[1].source, Source::Event);
+    // The projected slot at 20:00 is the event so it has the same UID as the event
+    let events = Calendar::from_events("tshwane-6-brooklyn", &info());
+    assert_eq!(calendar.uid(&outages[1]), events.uid(&events.outages()[0]));
+    let ics = calendar.to_string();
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+    assert!(ics.contains("UID:tshwane-6-brooklyn-20220808T020000Z@eskom-se-push-api\r\n"));
+    assert!(ics.contains("STATUS:TENTATIVE\r\n"));
+    assert!(ics.contains("SUMMARY:Stage 2\r\n"));
+  }
+
+  #[test]
+  fn escapes_and_folds_lines() {
+    assert_eq!(escape("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+    let calendar = Calendar::new("x", "é".repeat(60), Vec::new());
+    let ics = calendar.to_string();
+    for line in ics.split("\r\n") {
+      assert!(line.len() <= MAX_LINE_LEN, "{}", line);
+    }
+    assert!(ics.contains("\r\n é"));
+    let unfolded = ics.replace("\r\n ", "");
+    assert!(unfolded.contains(&format!(
+      "X-WR-CALNAME:Load shedding - {}\r\n",
+      "é".repeat(60)
+    )));
+  }
+}
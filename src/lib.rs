@@ -10,11 +10,11 @@
 //!
 //! ## API key as a variable
 //!
-//! ```rust
+//! ```rust,no_run
 //! use eskom_se_push_api::EskomAPI;
 //!
 //! fn main() {
-//!   let api = EskomAPI::new("XXXXXXXXXXXXXXXXXXXXXXXXX");
+//!   let api = EskomAPI::new("XXXXXXXXXXXXXXXXXXXXXXXXX".to_owned());
 //!   let resp = api.check_allowance();
 //!   match resp {
 //!     Ok(allowance) => {
@@ -33,7 +33,7 @@
 //! ## API key as an env variable
 //!
 //! The default env variable is `ESKOMSEPUSH_API_KEY`
-//! ```rust
+//! ```rust,no_run
 //! use eskom_se_push_api::EskomAPI;
 //!
 //! fn main() {
@@ -53,11 +53,11 @@
 //! }
 //! ```
 //!
-//! //!
+//!
 //! ## API key as an custom env variable
 //!
 //! Able to use custom env keys such as `MY_CUSTOM_KEY`
-//! ```rust
+//! ```rust,no_run
 //! use eskom_se_push_api::EskomAPI;
 //!
 //! fn main() {
@@ -77,9 +77,43 @@
 //! }
 //! ```
 //!
+//! ## Custom base URL
+//!
+//! All the clients and endpoint builders can be pointed at a different base URL such as a staging proxy,
+//! a caching gateway or a local mock server. The `ESKOMSEPUSH_BASE_URL` env variable is used by `new_with_env`
+//! ```rust,no_run
+//! use eskom_se_push_api::EskomAPI;
+//!
+//! fn main() {
+//!   let api = EskomAPI::new("XXXXXXXXXXXXXXXXXXXXXXXXX".to_owned())
+//!     .with_base_url("http://localhost:8080/business/2.0");
+//!   let resp = api.check_allowance();
+//! }
+//! ```
+//!
+//! ## Generic clients
+//!
+//! All the clients implement [`EskomClient`] (or [`EskomClientAsync`] for the async client) so code can be
+//! generic over the http backend and use a fake client in tests.
+//! ```rust,no_run
+//! use eskom_se_push_api::{EskomAPI, EskomClient};
+//!
+//! fn current_stage(api: &impl EskomClient) -> String {
+//!   match api.get_load_shedding_status() {
+//!     Ok(status) => status.eskom().stage.clone(),
+//!     Err(e) => format!("Error: {}", e),
+//!   }
+//! }
+//!
+//! fn main() {
+//!   let api = EskomAPI::new_with_env(None);
+//!   println!("Stage {}", current_stage(&api));
+//! }
+//! ```
+//!
 //! ## Features
 //!
-//! There are currently 4 features but some are used in combinations to enable certain functionality
+//! There are currently 8 features but some are used in combinations to enable certain functionality
 //!
 //! * `reqwest` and `async`: Adds an async reqwest client and response handler
 //!
@@ -87,35 +121,108 @@
 //!
 //! * `ureq`: Adds a ureq client and response handler
 //!
-//! None of the features are added by default
+//! * `async`: Adds the async traits eg [`EskomClientAsync`] and [`EndpointAsync`]
+//!
+//! * `mock-server`: Adds a mock EskomSePush server for offline integration testing
+//!
+//! * `config`: Adds the TOML config file with saved areas (`config`)
+//!
+//! * `feed-server`: Adds an HTTP server that serves iCalendar feeds of areas (`feed_server`)
+//!
+//! * `cli`: Builds the `esp` command line tool. Enables `ureq`, `config` and `feed-server`
+//!
+//! `ureq`, `reqwest`, `sync` and `async` are added by default
 
-pub use traits::Endpoint;
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+pub use traits::{Endpoint, EskomClient};
 #[cfg(any(feature = "async", doc))]
-pub use traits::EndpointAsync;
+pub use traits::{EndpointAsync, EskomClientAsync};
 extern crate thiserror;
 
 pub mod allowance;
 pub mod area_info;
 pub mod area_nearby;
 pub mod area_search;
+pub mod budget;
+pub mod cache;
+#[cfg(any(
+  feature = "ureq",
+  all(feature = "reqwest", any(feature = "sync", feature = "async")),
+  doc
+))]
+pub mod client_builder;
+#[cfg(feature = "config")]
+#[cfg_attr(docsrs, doc(cfg(feature = "config")))]
+pub mod config;
 pub mod constants;
+pub mod coordinates;
+pub mod disk_cache;
 pub mod errors;
+#[cfg(feature = "feed-server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "feed-server")))]
+pub mod feed_server;
+pub mod ical;
+#[cfg(feature = "mock-server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mock-server")))]
+pub mod mock_server;
+pub mod predict;
 #[cfg(any(all(feature = "async", feature = "reqwest"), doc))]
 pub mod reqwest_async_client;
 #[cfg(any(all(feature = "sync", feature = "reqwest"), doc))]
 pub mod reqwest_blocking_client;
+pub mod resolver;
+pub mod retry;
+pub mod schedule;
 pub mod status;
 pub mod topics_nearby;
 mod traits;
+pub mod transport;
 #[cfg(any(feature = "ureq", doc))]
 pub mod ureq_client;
 
+/// The default blocking client which uses the `ureq` Http client.
+/// Requires the `ureq` feature to be enabled
+#[cfg(any(feature = "ureq", doc))]
+pub type EskomAPI = ureq_client::UreqClient;
+
+/// Gets the Eskom-Se-Push token from an env variable.
+/// Uses the [dotenv](https://crates.io/crates/dotenv) crate so it will load .env files if available.
+/// `Note`: The default variable name is `ESKOMSEPUSH_API_KEY` if var_name is set to `None`.
 pub fn get_token_from_env(var_name: Option<&str>) -> Result<String, std::env::VarError> {
   dotenv::dotenv().ok();
-  let key = var_name.unwrap_or("ESKOMSEPUSH_API_KEY");
+  let key = var_name.unwrap_or(constants::TOKEN_ENV_KEY);
   std::env::var(key)
 }
 
+/// Gets the Eskom-Se-Push token from an env variable like [`get_token_from_env`] but with the name of the variable in the error
+pub fn try_get_token_from_env(var_name: Option<&str>) -> Result<String, errors::ConfigError> {
+  get_token_from_env(var_name).map_err(|source| errors::ConfigError::MissingVariable {
+    name: var_name.unwrap_or(constants::TOKEN_ENV_KEY).to_owned(),
+    source,
+  })
+}
+
+/// Checks the token only contains characters that can be sent in a header
+pub fn validate_token(token: &str) -> Result<(), errors::ConfigError> {
+  http::HeaderValue::from_str(token)
+    .map(|_| ())
+    .map_err(|source| errors::ConfigError::InvalidHeader {
+      name: constants::TOKEN_KEY.to_owned(),
+      source,
+    })
+}
+
+/// Gets the base URL of the API from an env variable, falling back to [`constants::BASE_URL`] if it isn't set.
+/// Useful for pointing the clients at a staging proxy, caching gateway or a local mock server.
+/// Uses the [dotenv](https://crates.io/crates/dotenv) crate so it will load .env files if available.
+/// `Note`: The default variable name is `ESKOMSEPUSH_BASE_URL` if var_name is set to `None`.
+pub fn get_base_url_from_env(var_name: Option<&str>) -> String {
+  dotenv::dotenv().ok();
+  let key = var_name.unwrap_or(constants::BASE_URL_ENV_KEY);
+  std::env::var(key).unwrap_or_else(|_| constants::BASE_URL.to_owned())
+}
+
 #[cfg(test)]
 mod tests {
   // use super::*;
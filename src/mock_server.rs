@@ -0,0 +1,394 @@
+//! A mock EskomSePush server for offline integration testing.
+//!
+//! The server runs on a background thread and serves canned responses for all the endpoints.
+//! It enforces the [`TOKEN_KEY`] header and can be scripted to return error responses.
+//! ```rust
+//! use eskom_se_push_api::{mock_server::MockServer, ureq_client::UreqClient};
+//!
+//! let server = MockServer::start("XXXXXXXXXXXXXXXXXXXXXXXXX");
+//! let api = UreqClient::new("XXXXXXXXXXXXXXXXXXXXXXXXX".to_owned()).with_base_url(server.base_url());
+//! let allowance = api.check_allowance().unwrap();
+//! assert_eq!(allowance.allowance.count, 12);
+//! ```
+//!
+//! # Optional
+//! Requires the `mock-server` feature to be enabled
+
+use std::{
+  collections::{HashMap, VecDeque},
+  net::SocketAddr,
+  sync::{Arc, Mutex},
+  thread::JoinHandle,
+};
+
+use crate::constants::TOKEN_KEY;
+
+/// Canned responses served by the [`MockServer`]
+pub mod fixtures {
+  /// Response for the `status` endpoint
+  pub const STATUS: &str = r#"{
+  "status": {
+    "capetown": {
+      "name": "Cape Town",
+      "next_stages": [
+        { "stage": "1", "stage_start_timestamp": "2022-08-08T22:00:00+02:00" },
+        { "stage": "0", "stage_start_timestamp": "2022-08-09T05:00:00+02:00" }
+      ],
+      "stage": "2",
+      "stage_updated": "2022-08-08T16:12:53.725852+02:00"
+    },
+    "eskom": {
+      "name": "National",
+      "next_stages": [
+        { "stage": "2", "stage_start_timestamp": "2022-08-08T16:00:00+02:00" },
+        { "stage": "4", "stage_start_timestamp": "2022-08-09T05:00:00+02:00" }
+      ],
+      "stage": "3",
+      "stage_updated": "2022-08-08T00:08:16.837063+02:00"
+    }
+  }
+}"#;
+
+  /// Response for the `area` endpoint
+  pub const AREA_INFO: &str = r#"{
+  "events": [
+    {
+      "end": "2022-08-08T22:30:00+02:00",
+      "note": "Stage 2",
+      "start": "2022-08-08T20:00:00+02:00"
+    },
+    {
+      "end": "2022-08-09T14:30:00+02:00",
+      "note": "Stage 4",
+      "start": "2022-08-09T12:00:00+02:00"
+    }
+  ],
+  "info": {
+    "name": "Brooklyn (6)",
+    "region": "City of Tshwane"
+  },
+  "schedule": {
+    "days": [
+      {
+        "date": "2022-08-08",
+        "name": "Monday",
+        "stages": [
+          [],
+          ["20:00-22:30"],
+          ["04:00-06:30", "20:00-22:30"],
+          ["04:00-06:30", "12:00-14:30", "20:00-22:30"]
+        ]
+      },
+      {
+        "date": "2022-08-09",
+        "name": "Tuesday",
+        "stages": [
+          ["22:00-00:30"],
+          ["22:00-00:30"],
+          ["06:00-08:30", "22:00-00:30"],
+          ["06:00-08:30", "12:00-14:30", "22:00-00:30"]
+        ]
+      }
+    ],
+    "source": "https://www.eskom.co.za/distribution/customer-service/outages/municipal-loadshedding-schedules/"
+  }
+}"#;
+
+  /// Response for the `areas_search` endpoint
+  pub const AREA_SEARCH: &str = r#"{
+  "areas": [
+    {
+      "id": "tshwane-6-brooklyn",
+      "name": "Brooklyn (6)",
+      "region": "City of Tshwane"
+    },
+    {
+      "id": "capetown-5-brooklyn",
+      "name": "Brooklyn (5)",
+      "region": "City of Cape Town"
+    }
+  ]
+}"#;
+
+  /// Response for the `areas_nearby` endpoint
+  pub const AREAS_NEARBY: &str = r#"{
+  "areas": [
+    {
+      "count": 2,
+      "id": "jhbcitypower2-9-fourways",
+      "name": "Fourways (9)",
+      "region": "JHB City Power"
+    }
+  ]
+}"#;
+
+  /// Response for the `topics_nearby` endpoint
+  pub const TOPICS_NEARBY: &str = r#"{
+  "topics": [
+    {
+      "active": "2022-08-08T18:38:10.279000+02:00",
+      "body": "Power out in the whole area",
+      "category": "electricity",
+      "distance": 1.23,
+      "followers": 4,
+      "timestamp": "2022-08-08T18:38:10.279000+02:00"
+    }
+  ]
+}"#;
+
+  /// Response for the `api_allowance` endpoint
+  pub const ALLOWANCE: &str = r#"{
+  "allowance": {
+    "count": 12,
+    "limit": 50,
+    "type": "daily"
+  }
+}"#;
+}
+
+/// A response the [`MockServer`] sends back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockResponse {
+  pub status: u16,
+  pub body: String,
+  pub headers: Vec<(String, String)>,
+}
+
+impl MockResponse {
+  /// A response with the given status and body
+  pub fn new(status: u16, body: impl Into<String>) -> Self {
+    MockResponse {
+      status,
+      body: body.into(),
+      headers: Vec::new(),
+    }
+  }
+
+  /// An error response with the given status and a JSON error body
+  pub fn status(status: u16) -> Self {
+    let message = match status {
+      400 => "Bad Request",
+      403 => "Not Authenticated",
+      404 => "Not Found",
+      429 => "Too Many Requests",
+      500..=599 => "Server Error",
+      _ => "Error",
+    };
+    MockResponse::new(status, format!(r#"{{"error": "{}"}}"#, message))
+  }
+
+  /// Adds a header to the response eg `Retry-After`
+  pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+    self.headers.push((name.into(), value.into()));
+    self
+  }
+}
+
+/// A request received by the [`MockServer`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedRequest {
+  pub method: String,
+  /// The path and query of the request eg `/area?id=tshwane-6-brooklyn`
+  pub url: String,
+  /// The value of the [`TOKEN_KEY`] header if it was sent
+  pub token: Option<String>,
+  /// All the headers of the request as name and value
+  pub headers: Vec<(String, String)>,
+}
+
+impl RecordedRequest {
+  /// The path of the request without the query
+  pub fn path(&self) -> &str {
+    self.url.split('?').next().unwrap_or_default()
+  }
+
+  /// The value of the header (case insensitive) if it was sent
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self
+      .headers
+      .iter()
+      .find(|(header, _)| header.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value.as_str())
+  }
+}
+
+#[derive(Default)]
+struct MockState {
+  /// Responses that are returned for every request to the endpoint
+  responses: HashMap<String, MockResponse>,
+  /// Responses that are returned once, before falling back to `responses`
+  queued: HashMap<String, VecDeque<MockResponse>>,
+  requests: Vec<RecordedRequest>,
+}
+
+/// A mock EskomSePush server running on a background thread.
+/// The server is shut down when it is dropped.
+pub struct MockServer {
+  server: Arc<tiny_http::Server>,
+  state: Arc<Mutex<MockState>>,
+  addr: SocketAddr,
+  handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+  /// Starts the mock server on a random local port.
+  /// `token` is the only token the server accepts, any other token returns `403`
+  pub fn start(token: impl Into<String>) -> Self {
+    let token = token.into();
+    let server =
+      Arc::new(tiny_http::Server::http("127.0.0.1:0").expect("Failed to bind mock server"));
+    let addr = server
+      .server_addr()
+      .to_ip()
+      .expect("Mock server is not listening on an IP address");
+    let state = Arc::new(Mutex::new(MockState::default()));
+
+    let handle = {
+      let server = server.clone();
+      let state = state.clone();
+      std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+          let response = handle_request(&request, &token, &state);
+          let mut reply = tiny_http::Response::from_string(response.body)
+            .with_status_code(response.status)
+            .with_header(header("Content-Type", "application/json"));
+          for (name, value) in &response.headers {
+            reply.add_header(header(name, value));
+          }
+          let _ = request.respond(reply);
+        }
+      })
+    };
+
+    MockServer {
+      server,
+      state,
+      addr,
+      handle: Some(handle),
+    }
+  }
+
+  /// The base URL to pass to the clients or endpoint builders
+  pub fn base_url(&self) -> String {
+    format!("http://{}", self.addr)
+  }
+
+  /// Always returns `response` for the endpoint eg `area` or `status`
+  pub fn respond_with(&self, endpoint: &str, response: MockResponse) {
+    self
+      .state
+      .lock()
+      .unwrap()
+      .responses
+      .insert(endpoint.trim_matches('/').to_owned(), response);
+  }
+
+  /// Returns `response` for the next request to the endpoint only.
+  /// Multiple calls are returned in order.
+  pub fn respond_once(&self, endpoint: &str, response: MockResponse) {
+    self
+      .state
+      .lock()
+      .unwrap()
+      .queued
+      .entry(endpoint.trim_matches('/').to_owned())
+      .or_default()
+      .push_back(response);
+  }
+
+  /// Removes all scripted responses so the canned responses are returned again
+  pub fn reset(&self) {
+    let mut state = self.state.lock().unwrap();
+    state.responses.clear();
+    state.queued.clear();
+  }
+
+  /// All the requests the server has received
+  pub fn requests(&self) -> Vec<RecordedRequest> {
+    self.state.lock().unwrap().requests.clone()
+  }
+
+  /// The number of requests made to the endpoint eg `area` or `status`
+  pub fn hits(&self, endpoint: &str) -> usize {
+    let path = format!("/{}", endpoint.trim_matches('/'));
+    self
+      .state
+      .lock()
+      .unwrap()
+      .requests
+      .iter()
+      .filter(|r| r.path() == path)
+      .count()
+  }
+}
+
+impl Drop for MockServer {
+  fn drop(&mut self) {
+    self.server.unblock();
+    if let Some(handle) = self.handle.take() {
+      let _ = handle.join();
+    }
+  }
+}
+
+fn header(name: &str, value: &str) -> tiny_http::Header {
+  tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("Invalid mock header")
+}
+
+fn handle_request(
+  request: &tiny_http::Request,
+  token: &str,
+  state: &Mutex<MockState>,
+) -> MockResponse {
+  let recorded = RecordedRequest {
+    method: request.method().to_string(),
+    url: request.url().to_owned(),
+    token: request
+      .headers()
+      .iter()
+      .find(|h| h.field.equiv(TOKEN_KEY))
+      .map(|h| h.value.to_string()),
+    headers: request
+      .headers()
+      .iter()
+      .map(|h| (h.field.to_string(), h.value.to_string()))
+      .collect(),
+  };
+  let endpoint = recorded.path().trim_matches('/').to_owned();
+  let query: HashMap<String, String> = url::form_urlencoded::parse(
+    recorded
+      .url
+      .split_once('?')
+      .map(|(_, q)| q)
+      .unwrap_or_default()
+      .as_bytes(),
+  )
+  .into_owned()
+  .collect();
+  let authorised = recorded.token.as_deref() == Some(token);
+
+  let mut state = state.lock().unwrap();
+  state.requests.push(recorded);
+
+  if !authorised {
+    return MockResponse::status(403);
+  }
+  if let Some(response) = state.queued.get_mut(&endpoint).and_then(|q| q.pop_front()) {
+    return response;
+  }
+  if let Some(response) = state.responses.get(&endpoint) {
+    return response.clone();
+  }
+
+  let has = |key: &str| query.get(key).is_some_and(|v| !v.trim().is_empty());
+  match endpoint.as_str() {
+    "status" => MockResponse::new(200, fixtures::STATUS),
+    "area" if has("id") => MockResponse::new(200, fixtures::AREA_INFO),
+    "areas_search" if has("text") => MockResponse::new(200, fixtures::AREA_SEARCH),
+    "areas_nearby" if has("lat") && has("long") => MockResponse::new(200, fixtures::AREAS_NEARBY),
+    "topics_nearby" if has("lat") && has("long") => MockResponse::new(200, fixtures::TOPICS_NEARBY),
+    "api_allowance" => MockResponse::new(200, fixtures::ALLOWANCE),
+    "area" | "areas_search" | "areas_nearby" | "topics_nearby" => MockResponse::status(400),
+    _ => MockResponse::status(404),
+  }
+}
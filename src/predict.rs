@@ -0,0 +1,226 @@
+//! Predicts the outages of an area by combining the announced stage changes with the area's schedule.
+//!
+//! The `events` of an [`AreaInfo`] are the outages the API has confirmed. The rest are projected from the
+//! area's schedule using the current stage and the `next_stages` of the [`LoadsheddingStatus`].
+//! ```rust,no_run
+//! use eskom_se_push_api::{
+//!   predict::predict, resolver::StatusResolver, ureq_client::UreqClient,
+//! };
+//!
+//! let api = UreqClient::new_with_env(None);
+//! let status = api.get_load_shedding_status().unwrap();
+//! let info = api.get_area_info("tshwane-6-brooklyn").unwrap();
+//! let applicable = StatusResolver::default()
+//!   .resolve_info(&status, &info.info)
+//!   .unwrap();
+//! for outage in predict(applicable, &info, 3).unwrap() {
+//!   println!("{} until {} ({:?})", outage.start, outage.end, outage.source);
+//! }
+//! ```
+
+use chrono::{DateTime, Duration, FixedOffset};
+
+use crate::{
+  area_info::{AreaInfo, Event},
+  errors::ScheduleError,
+  schedule::{sast, OutageWindow},
+  status::{LoadsheddingStatus, Stage},
+};
+
+/// Where a predicted outage came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Source {
+  /// One of the `events` of the area returned by the API
+  Event,
+  /// Projected from the area's schedule and the announced stages
+  Projected,
+}
+
+/// A period the power is expected to be off in an area
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PredictedOutage {
+  pub start: DateTime<FixedOffset>,
+  pub end: DateTime<FixedOffset>,
+  pub stage: Stage,
+  /// The note of the event as the API sent it eg `Stage 2 (TESTING: current)`
+  /// or the stage eg `Stage 2` if the outage was projected
+  pub note: String,
+  pub source: Source,
+}
+
+impl PredictedOutage {
+  /// How long the power is expected to be off
+  pub fn duration(&self) -> Duration {
+    self.end - self.start
+  }
+}
+
+impl From<&Event> for PredictedOutage {
+  fn from(event: &Event) -> Self {
+    PredictedOutage {
+      start: event.start,
+      end: event.end,
+      stage: event.note.clone(),
+      note: event.raw_note().into_owned(),
+      source: Source::Event,
+    }
+  }
+}
+
+/// Predicts the outages of the area over the first `days` days of its schedule.
+///
+/// The `status` should be the one that applies to the area ie the national `eskom` status or a municipal override
+/// such as `capetown`. See [`StatusResolver`](crate::resolver::StatusResolver).
+/// The events of the area take precedence, projected outages are only added for the times not covered by an event.
+/// The outages are sorted by when they start.
+pub fn predict(
+  status: &LoadsheddingStatus,
+  info: &AreaInfo,
+  days: usize,
+) -> Result<Vec<PredictedOutage>, ScheduleError> {
+  let projected = info.schedule.project(&status.timeline(), days)?;
+  Ok(combine(&info.events, projected))
+}
+
+/// Combines the events with the parts of the projected windows they don't cover, sorted by when they start
+pub(crate) fn combine(events: &[Event], projected: Vec<OutageWindow>) -> Vec<PredictedOutage> {
+  let mut events: Vec<PredictedOutage> = events.iter().map(PredictedOutage::from).collect();
+  events.sort_by_key(|event| event.start);
+  events.dedup();
+
+  let mut outages = events.clone();
+  for window in projected {
+    outages.extend(uncovered(&window, &events));
+  }
+  outages.sort_by_key(|outage| (outage.start, outage.end));
+  outages
+}
+
+/// The parts of the window that aren't covered by any of the sorted events
+fn uncovered(window: &OutageWindow, events: &[PredictedOutage]) -> Vec<PredictedOutage> {
+  let projected = |start: DateTime<FixedOffset>, end: DateTime<FixedOffset>| PredictedOutage {
+    start: start.with_timezone(&sast()),
+    end: end.with_timezone(&sast()),
+    stage: window.stage.clone(),
+    note: window.stage.to_string(),
+    source: Source::Projected,
+  };
+  let mut parts = Vec::new();
+  let mut cursor = window.start;
+  for event in events
+    .iter()
+    .filter(|event| event.start < window.end && event.end > window.start)
+  {
+    if event.start > cursor {
+      parts.push(projected(cursor, event.start));
+    }
+    cursor = cursor.max(event.end);
+  }
+  if cursor < window.end {
+    parts.push(projected(cursor, window.end));
+  }
+  parts
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    area_info::{Day, Schedule},
+    status::NextStage,
+  };
+
+  fn time(time: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(time).unwrap()
+  }
+
+  fn info() -> AreaInfo {
+    let day = |date: &str, name: &str| Day {
+      date: date.to_owned(),
+      name: name.to_owned(),
+      stages: vec![
+        vec!["04:00-06:30".to_owned()],
+        vec!["04:00-06:30".to_owned(), "20:00-22:30".to_owned()],
+        vec!["04:00-06:30".to_owned(), "20:00-22:30".to_owned()],
+        vec!["04:00-06:30".to_owned(), "20:00-00:30".to_owned()],
+      ],
+    };
+    AreaInfo {
+      events: vec![Event::new(
+        time("2022-08-08T20:00:00+02:00"),
+        time("2022-08-08T22:30:00+02:00"),
+        "Stage 2",
+      )],
+      schedule: Schedule {
+        days: vec![day("2022-08-08", "Monday"), day("2022-08-09", "Tuesday")],
+        source: String::new(),
+      },
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn events_take_precedence_over_projections() {
+    let status = LoadsheddingStatus {
+      stage: "2".to_owned(),
+      next_stages: vec![NextStage {
+        stage: "4".to_owned(),
+        stage_start_timestamp: time("2022-08-08T22:00:00+02:00").into(),
+      }],
+      ..Default::default()
+    };
+    let outages = predict(&status, &info(), 2).unwrap();
+    let summary: Vec<_> = outages
+      .iter()
+      .map(|outage| {
+        (
+          outage.start.to_rfc3339(),
+          outage.end.to_rfc3339(),
+          outage.source,
+        )
+      })
+      .collect();
+    assert_eq!(
+      summary,
+      vec![
+        (
+          "2022-08-08T04:00:00+02:00".to_owned(),
+          "2022-08-08T06:30:00+02:00".to_owned(),
+          Source::Projected
+        ),
+        (
+          "2022-08-08T20:00:00+02:00".to_owned(),
+          "2022-08-08T22:30:00+02:00".to_owned(),
+          Source::Event
+        ),
+        (
+          "2022-08-08T22:30:00+02:00".to_owned(),
+          "2022-08-09T00:30:00+02:00".to_owned(),
+          Source::Projected
+        ),
+        (
+          "2022-08-09T04:00:00+02:00".to_owned(),
+          "2022-08-09T06:30:00+02:00".to_owned(),
+          Source::Projected
+        ),
+        (
+          "2022-08-09T20:00:00+02:00".to_owned(),
+          "2022-08-10T00:30:00+02:00".to_owned(),
+          Source::Projected
+        ),
+      ]
+    );
+    assert_eq!(outages[2].stage, Stage::Stage4);
+  }
+
+  #[test]
+  fn no_load_shedding_only_returns_events() {
+    let status = LoadsheddingStatus {
+      stage: "0".to_owned(),
+      ..Default::default()
+    };
+    let outages = predict(&status, &info(), 2).unwrap();
+    assert_eq!(outages.len(), 1);
+    assert_eq!(outages[0].source, Source::Event);
+  }
+}
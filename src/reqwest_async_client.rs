@@ -2,184 +2,342 @@
 //!
 //! # Optional
 //! Requires the `reqwest` and `async` features to be enabled
-use http::{header, StatusCode};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::HeaderMap;
 use serde::de::DeserializeOwned;
 
 use crate::{
-  allowance::{AllowanceCheck, AllowanceCheckURL},
-  area_info::{AreaInfo, AreaInfoURLBuilder},
+  allowance::{AllowanceCheck, AllowanceCheckURLBuilder},
+  area_info::{AreaInfo, AreaInfoURLBuilder, TestMode},
   area_nearby::{AreaNearby, AreasNearbyURLBuilder},
   area_search::{AreaSearch, AreaSearchURLBuilder},
-  constants::TOKEN_KEY,
-  errors::{APIError, HttpError},
-  get_token_from_env,
-  status::{EskomStatus, EskomStatusUrl},
+  cache::{Cache, ResponseCache},
+  client_builder::ClientBuilder,
+  constants::BASE_URL,
+  errors::{ConfigError, ErrorContext, HttpError},
+  get_base_url_from_env,
+  retry::{Retry, RetryPolicy},
+  status::{EskomStatus, EskomStatusUrlBuilder},
   topics_nearby::{TopicsNearby, TopicsNearbyUrlBuilder},
-  EndpointAsync,
+  transport::{handle_response_with_context, AsyncTransport, DefaultHeaders},
+  try_get_token_from_env, EndpointAsync, EskomClientAsync,
 };
 
 pub struct ReqwestAsyncCLient {
   client: reqwest::Client,
+  token: String,
+  base_url: String,
+  retry_policy: RetryPolicy,
+  cache: Option<Arc<dyn ResponseCache>>,
+  headers: HeaderMap,
 }
 
 impl ReqwestAsyncCLient {
-  /// Create new client using the `reqwest::blocking` Http client
+  /// Create new client using the `reqwest` Http client
   /// `token` is the Eskom API token
   pub fn new(token: String) -> Self {
-    let mut headers = header::HeaderMap::new();
-    headers.insert(
-      TOKEN_KEY,
-      header::HeaderValue::from_str(token.as_str()).unwrap(),
-    );
+    ReqwestAsyncCLient::from_parts(reqwest::Client::new(), token, HeaderMap::new())
+  }
 
+  fn from_parts(client: reqwest::Client, token: String, headers: HeaderMap) -> Self {
     ReqwestAsyncCLient {
-      client: reqwest::ClientBuilder::new()
-        .default_headers(headers)
-        .build()
-        .unwrap(),
+      client,
+      token,
+      base_url: BASE_URL.to_owned(),
+      retry_policy: RetryPolicy::never(),
+      cache: None,
+      headers,
     }
   }
 
+  /// Create new client like [`ReqwestAsyncCLient::new`] but returns an error instead of panicking
+  /// if the token contains characters that aren't allowed in a header or the http client can't be built (eg TLS initialisation fails)
+  pub fn try_new(token: String) -> Result<Self, ConfigError> {
+    ClientBuilder::new(token).build_reqwest_async()
+  }
+
   /// Creates new instance of Eskom API using token as a env variable.
   /// Uses the [dotenv](https://crates.io/crates/dotenv) crate so it will load .env files if available.
+  /// The base URL is also read from the `ESKOMSEPUSH_BASE_URL` env variable if it is set.
   /// `Note`: The default variable name is `ESKOMSEPUSH_API_KEY` if var_name is set to `None`.
-  /// `Note`: It will panic the env variable doesn't exist.
+  /// `Note`: It will panic the env variable doesn't exist. Use [`ReqwestAsyncCLient::try_from_env`] to handle the error instead.
   pub fn new_with_env(var_name: Option<&str>) -> Self {
-    match get_token_from_env(var_name) {
-      Ok(val) => {
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-          TOKEN_KEY,
-          header::HeaderValue::from_str(val.as_str()).unwrap(),
-        );
-
-        ReqwestAsyncCLient {
-          client: reqwest::ClientBuilder::new()
-            .default_headers(headers)
-            .build()
-            .unwrap(),
-        }
-      }
+    match ReqwestAsyncCLient::try_from_env(var_name) {
+      Ok(client) => client,
       Err(e) => panic!("Error: {}", e),
     }
   }
 
+  /// Creates new instance of Eskom API using token as a env variable like [`ReqwestAsyncCLient::new_with_env`]
+  /// but returns an error if the variable doesn't exist or the client can't be created
+  pub fn try_from_env(var_name: Option<&str>) -> Result<Self, ConfigError> {
+    let token = try_get_token_from_env(var_name)?;
+    Ok(ReqwestAsyncCLient::try_new(token)?.with_base_url(get_base_url_from_env(None)))
+  }
+
+  /// Overrides the base URL of the API eg a staging proxy, caching gateway or a local mock server.
+  /// Defaults to [`BASE_URL`](crate::constants::BASE_URL)
+  pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+    self.base_url = base_url.into();
+    self
+  }
+
+  /// Retries transient failures using the policy. By default requests aren't retried
+  pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+    self.retry_policy = retry_policy;
+    self
+  }
+
+  /// Serves responses from the cache while they are fresh instead of using the quota.
+  /// The cache can be shared between clients
+  pub fn with_cache(mut self, cache: Arc<dyn ResponseCache>) -> Self {
+    self.cache = Some(cache);
+    self
+  }
+
+  /// The transport used to make the API calls
+  fn transport(&self) -> Cache<'_, Retry<'_, DefaultHeaders<'_, &reqwest::Client>>> {
+    Cache::new(
+      Retry::new(
+        DefaultHeaders::new(&self.client, &self.headers),
+        &self.retry_policy,
+      ),
+      self.cache.as_deref(),
+    )
+  }
+
   /// The current and next loadshedding statuses for South Africa and (Optional) municipal overrides
   /// `eskom` is the National status
   /// Other keys in the `status` refer to different municipalities and potential overrides from the National status; most typically present is the key for `capetown`
   pub async fn get_load_shedding_status(&self) -> Result<EskomStatus, HttpError> {
-    let c = EskomStatusUrl::default();
-    c.reqwest_client_async(&self.client).await
+    let c = EskomStatusUrlBuilder::default()
+      .base_url(self.base_url.as_str())
+      .build()
+      .map_err(|e| HttpError::UnknownError(e.to_string()))?;
+    c.query_async(&self.transport(), &self.token).await
   }
 
   /// Obtain the `area_id` from Area Find or Area Search and use with this request. This single request has everything you need to monitor upcoming loadshedding events for the chosen suburb.
   pub async fn get_area_info(&self, area_id: &str) -> Result<AreaInfo, HttpError> {
-    let t = AreaInfoURLBuilder::default()
-      .area_id(area_id.to_owned())
-      .build()
-      .map_err(|_| HttpError::AreaIdNotSet)?;
-    t.reqwest_client_async(&self.client).await
+    self.area_info(area_id, None).await
+  }
+
+  /// Gets the info of the area, with synthetic events if the test mode is set
+  async fn area_info(&self, area_id: &str, test: Option<TestMode>) -> Result<AreaInfo, HttpError> {
+    let mut builder = AreaInfoURLBuilder::default();
+    builder
+      .base_url(self.base_url.as_str())
+      .area_id(area_id.to_owned());
+    if let Some(test) = test {
+      builder.test(test);
+    }
+    let t = builder.build().map_err(|_| HttpError::AreaIdNotSet)?;
+    t.query_async(&self.transport(), &self.token).await
   }
 
   /// Find areas based on GPS coordinates (latitude and longitude).
   /// The first area returned is typically the best choice for the coordinates - as it's closest to the GPS coordinates provided. However it could be that you are in the second or third area.
   pub async fn areas_nearby(&self, lat: f32, long: f32) -> Result<AreaNearby, HttpError> {
     let t = AreasNearbyURLBuilder::default()
+      .base_url(self.base_url.as_str())
       .latitude(lat)
       .longitude(long)
       .build()
       .map_err(|_| HttpError::LongitudeOrLatitudeNotSet {
-        longitude: lat,
-        latitude: long,
+        longitude: long,
+        latitude: lat,
       })?;
-    t.reqwest_client_async(&self.client).await
+    t.query_async(&self.transport(), &self.token).await
   }
 
   /// Search area based on text
   pub async fn areas_search(&self, search_term: &str) -> Result<AreaSearch, HttpError> {
     let t = AreaSearchURLBuilder::default()
+      .base_url(self.base_url.as_str())
       .search_term(search_term)
       .build()
       .map_err(|_| HttpError::SearchTextNotSet)?;
-    t.reqwest_client_async(&self.client).await
+    t.query_async(&self.transport(), &self.token).await
   }
 
   /// Find topics created by users based on GPS coordinates (latitude and longitude). Can use this to detect if there is a potential outage/problem nearby
   pub async fn topics_nearby(&self, lat: f32, long: f32) -> Result<TopicsNearby, HttpError> {
     let t = TopicsNearbyUrlBuilder::default()
+      .base_url(self.base_url.as_str())
       .latitude(lat)
       .longitude(long)
       .build()
       .map_err(|_| HttpError::LongitudeOrLatitudeNotSet {
-        longitude: lat,
-        latitude: long,
+        longitude: long,
+        latitude: lat,
       })?;
-    t.reqwest_client_async(&self.client).await
+    t.query_async(&self.transport(), &self.token).await
   }
 
   /// Check allowance allocated for token
   /// `NOTE`: This call doesn't count towards your quota.
   pub async fn check_allowance(&self) -> Result<AllowanceCheck, HttpError> {
-    let t = AllowanceCheckURL::default();
-    t.reqwest_client_async(&self.client).await
+    let t = AllowanceCheckURLBuilder::default()
+      .base_url(self.base_url.as_str())
+      .build()
+      .map_err(|e| HttpError::UnknownError(e.to_string()))?;
+    t.query_async(&self.transport(), &self.token).await
+  }
+}
+
+impl ClientBuilder {
+  /// Builds a client using the `reqwest` Http client
+  pub fn build_reqwest_async(self) -> Result<ReqwestAsyncCLient, ConfigError> {
+    let mut client = reqwest::Client::builder();
+    if let Some(timeout) = self.connect_timeout {
+      client = client.connect_timeout(timeout);
+    }
+    if let Some(timeout) = self.read_timeout {
+      client = client.timeout(timeout);
+    }
+    if let Some(proxy) = &self.proxy {
+      let proxy = reqwest::Proxy::all(proxy).map_err(|e| ConfigError::ClientBuild(Box::new(e)))?;
+      client = client.proxy(proxy);
+    }
+    let client = client
+      .build()
+      .map_err(|e| ConfigError::ClientBuild(Box::new(e)))?;
+    let headers = self.default_headers()?;
+    Ok(ReqwestAsyncCLient::from_parts(client, self.token, headers))
+  }
+
+  /// Builds a client that uses an existing `reqwest` client.
+  /// `Note`: The timeouts and proxy need to be set on the client, an error is returned if they are set on the builder
+  pub fn wrap_reqwest_async(
+    self,
+    client: reqwest::Client,
+  ) -> Result<ReqwestAsyncCLient, ConfigError> {
+    self.check_wrappable()?;
+    let headers = self.default_headers()?;
+    Ok(ReqwestAsyncCLient::from_parts(client, self.token, headers))
+  }
+}
+
+#[async_trait]
+impl EskomClientAsync for ReqwestAsyncCLient {
+  async fn get_load_shedding_status(&self) -> Result<EskomStatus, HttpError> {
+    ReqwestAsyncCLient::get_load_shedding_status(self).await
+  }
+
+  async fn get_area_info(&self, area_id: &str) -> Result<AreaInfo, HttpError> {
+    ReqwestAsyncCLient::get_area_info(self, area_id).await
+  }
+
+  async fn get_area_info_with_test(
+    &self,
+    area_id: &str,
+    test: TestMode,
+  ) -> Result<AreaInfo, HttpError> {
+    self.area_info(area_id, Some(test)).await
+  }
+
+  async fn areas_nearby(&self, lat: f32, long: f32) -> Result<AreaNearby, HttpError> {
+    ReqwestAsyncCLient::areas_nearby(self, lat, long).await
+  }
+
+  async fn areas_search(&self, search_term: &str) -> Result<AreaSearch, HttpError> {
+    ReqwestAsyncCLient::areas_search(self, search_term).await
+  }
+
+  async fn topics_nearby(&self, lat: f32, long: f32) -> Result<TopicsNearby, HttpError> {
+    ReqwestAsyncCLient::topics_nearby(self, lat, long).await
+  }
+
+  async fn check_allowance(&self) -> Result<AllowanceCheck, HttpError> {
+    ReqwestAsyncCLient::check_allowance(self).await
   }
 }
 
 /// A response handler for `reqwest` with async to map the response to the given structure or relevant error
-/// ```rust
-/// let statusUrl = EskomStatusUrlBuilder::default().build().unwrap();
+/// ```rust,no_run
+/// use eskom_se_push_api::{
+///   constants::TOKEN_KEY,
+///   reqwest_async_client::handle_reqwest_response,
+///   status::{EskomStatus, EskomStatusUrlBuilder},
+///   Endpoint,
+/// };
+/// use http::header;
+///
+/// async fn status() {
+///   let status_url = EskomStatusUrlBuilder::default().build().unwrap();
 ///
-/// let mut headers = header::HeaderMap::new();
-/// headers.insert(TOKEN_KEY, header::HeaderValue::from_str(token).unwrap());
-/// let client = reqwest::ClientBuilder::new().
-///   default_headers(headers).build().unwrap();
+///   let mut headers = header::HeaderMap::new();
+///   headers.insert(TOKEN_KEY, header::HeaderValue::from_str("YOUR-TOKEN").unwrap());
+///   let client = reqwest::ClientBuilder::new()
+///     .default_headers(headers)
+///     .build()
+///     .unwrap();
 ///
-/// let api_response = client.get(url_endpoint.as_str()).send().await;
-/// let response = handle_ureq_response::<EskomStatus>(api_response).await;
+///   let api_response = client.get(status_url.url().unwrap().as_str()).send().await;
+///   let response = handle_reqwest_response::<EskomStatus>(api_response).await;
+/// }
 /// ```
-/// `response` is the ureq API response
+/// `response` is the reqwest API response
 /// NOTE
 /// Requires the `reqwest` and `async` features to be enabled
 pub async fn handle_reqwest_response<T: DeserializeOwned>(
   response: Result<reqwest::Response, reqwest::Error>,
 ) -> Result<T, HttpError> {
-  match response {
-    Ok(resp) => {
-      let status_code = resp.status();
-      if status_code.is_server_error() {
-        Err(HttpError::ResponseError(
-          resp.error_for_status().unwrap_err(),
-        ))
-      } else {
-        match status_code {
-          StatusCode::BAD_REQUEST => Err(HttpError::APIError(APIError::BadRequest)),
-          StatusCode::FORBIDDEN => Err(HttpError::APIError(APIError::Forbidden)),
-          StatusCode::NOT_FOUND => Err(HttpError::APIError(APIError::NotFound)),
-          StatusCode::TOO_MANY_REQUESTS => Err(HttpError::APIError(APIError::TooManyRequests)),
-          _ => {
-            let r = resp.json::<T>().await;
-            match r {
-              Ok(r) => Ok(r),
-              Err(e) => {
-                if e.is_decode() {
-                  Err(HttpError::ResponseError(e))
-                } else {
-                  Err(HttpError::Unknown)
-                }
-              }
-            }
-          }
-        }
-      }
+  let response = response.map_err(request_error)?;
+  let context = ErrorContext::for_url(response.url().as_str());
+  handle_response_with_context(into_http_response(response).await?, context)
+}
+
+#[async_trait]
+impl AsyncTransport for reqwest::Client {
+  async fn send(&self, request: http::Request<Bytes>) -> Result<http::Response<Bytes>, HttpError> {
+    let (parts, body) = request.into_parts();
+    let response = self
+      .request(parts.method, parts.uri.to_string())
+      .headers(parts.headers)
+      .body(body)
+      .send()
+      .await
+      .map_err(request_error)?;
+    into_http_response(response).await
+  }
+}
+
+/// Converts the `reqwest` response into a [`http::Response`] by reading the whole body
+async fn into_http_response(
+  response: reqwest::Response,
+) -> Result<http::Response<Bytes>, HttpError> {
+  let mut builder = http::Response::builder().status(response.status());
+  if let Some(headers) = builder.headers_mut() {
+    headers.extend(response.headers().clone());
+  }
+  let body = response.bytes().await.map_err(request_error)?;
+  Ok(builder.body(body)?)
+}
+
+fn request_error(err: reqwest::Error) -> HttpError {
+  let mut context = err
+    .url()
+    .map(|url| ErrorContext::for_url(url.as_str()))
+    .unwrap_or_default();
+  context.status = err.status();
+  if err.is_timeout() {
+    HttpError::Timeout {
+      context,
+      source: Some(Box::new(err)),
+    }
+  } else if err.is_status() || err.is_decode() || err.is_body() {
+    HttpError::InvalidResponse {
+      context,
+      source: Box::new(err),
     }
-    Err(err) => {
-      if err.is_timeout() {
-        Err(HttpError::Timeout)
-      } else if err.is_status() {
-        Err(HttpError::ResponseError(err))
-      } else {
-        Err(HttpError::NoInternet)
-      }
+  } else {
+    HttpError::NoInternet {
+      context,
+      source: Some(Box::new(err)),
     }
   }
 }
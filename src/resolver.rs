@@ -0,0 +1,193 @@
+//! Finds the load shedding status that applies to an area.
+//!
+//! [`EskomStatus`] has the national status under `eskom` and municipal overrides under other keys
+//! (eg `capetown`) for municipalities that don't follow the national stage.
+//! [`StatusResolver`] maps the region or id of an area to the key of its status and falls back to the national one.
+//! ```rust,no_run
+//! use eskom_se_push_api::{resolver::StatusResolver, ureq_client::UreqClient};
+//!
+//! let api = UreqClient::new_with_env(None);
+//! let status = api.get_load_shedding_status().unwrap();
+//! let area = &api.areas_search("brooklyn").unwrap().areas[0];
+//! let resolver = StatusResolver::default().with_region("City of Johannesburg", "joburg");
+//! let applicable = resolver.resolve_area(&status, area).unwrap();
+//! println!("{} is at stage {}", area.name, applicable.stage);
+//! ```
+
+use crate::{
+  area_info::Info,
+  area_search::Area,
+  status::{EskomStatus, LoadsheddingStatus},
+};
+
+/// The key of the national status
+pub const NATIONAL_KEY: &str = "eskom";
+
+/// How an area is matched to a status
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Matcher {
+  /// The region of the area eg `City of Cape Town`. Case insensitive
+  Region(String),
+  /// The start of the id of the area eg `capetown-`
+  IdPrefix(String),
+}
+
+impl Matcher {
+  fn matches(&self, region: &str, id: Option<&str>) -> bool {
+    match self {
+      Matcher::Region(expected) => expected.eq_ignore_ascii_case(region.trim()),
+      Matcher::IdPrefix(prefix) => id.is_some_and(|id| id.starts_with(prefix.as_str())),
+    }
+  }
+}
+
+/// Maps areas to the key of the status that applies to them.
+///
+/// The default resolver maps `City of Cape Town` (and ids starting with `capetown-`) to `capetown`
+/// and everything else to the national `eskom` status.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusResolver {
+  rules: Vec<(Matcher, String)>,
+  fallback: String,
+}
+
+impl Default for StatusResolver {
+  fn default() -> Self {
+    StatusResolver::empty()
+      .with_region("City of Cape Town", "capetown")
+      .with_id_prefix("capetown-", "capetown")
+  }
+}
+
+impl StatusResolver {
+  /// A resolver without any of the built-in rules that always returns the national status
+  pub fn empty() -> Self {
+    StatusResolver {
+      rules: Vec::new(),
+      fallback: NATIONAL_KEY.to_owned(),
+    }
+  }
+
+  /// Uses the status `key` for areas in the region. Rules are checked in the order they are added
+  pub fn with_region(mut self, region: impl Into<String>, key: impl Into<String>) -> Self {
+    let region = region.into().trim().to_owned();
+    self.rules.push((Matcher::Region(region), key.into()));
+    self
+  }
+
+  /// Uses the status `key` for areas whose id starts with the prefix. Rules are checked in the order they are added
+  pub fn with_id_prefix(mut self, prefix: impl Into<String>, key: impl Into<String>) -> Self {
+    self
+      .rules
+      .push((Matcher::IdPrefix(prefix.into()), key.into()));
+    self
+  }
+
+  /// The status key used when no rule matches. Default is `eskom`
+  pub fn with_fallback(mut self, key: impl Into<String>) -> Self {
+    self.fallback = key.into();
+    self
+  }
+
+  /// The status key for the area
+  pub fn key_for(&self, region: &str, id: Option<&str>) -> &str {
+    self
+      .rules
+      .iter()
+      .find(|(matcher, _)| matcher.matches(region, id))
+      .map_or(self.fallback.as_str(), |(_, key)| key.as_str())
+  }
+
+  /// The status that applies to the area. Falls back to the national status if the status for the area
+  /// isn't in `status` (eg the municipality currently follows the national stage)
+  pub fn resolve<'a>(
+    &self,
+    status: &'a EskomStatus,
+    region: &str,
+    id: Option<&str>,
+  ) -> Option<&'a LoadsheddingStatus> {
+    status
+      .area(self.key_for(region, id))
+      .or_else(|| status.area(&self.fallback))
+      .or_else(|| status.area(NATIONAL_KEY))
+  }
+
+  /// The status that applies to an area from a search
+  pub fn resolve_area<'a>(
+    &self,
+    status: &'a EskomStatus,
+    area: &Area,
+  ) -> Option<&'a LoadsheddingStatus> {
+    self.resolve(status, &area.region, Some(&area.id))
+  }
+
+  /// The status that applies to the area of an [`AreaInfo`](crate::area_info::AreaInfo)
+  pub fn resolve_info<'a>(
+    &self,
+    status: &'a EskomStatus,
+    info: &Info,
+  ) -> Option<&'a LoadsheddingStatus> {
+    self.resolve(status, &info.region, None)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn status() -> EskomStatus {
+    let status = |name: &str, stage: &str| LoadsheddingStatus {
+      name: name.to_owned(),
+      stage: stage.to_owned(),
+      ..Default::default()
+    };
+    EskomStatus {
+      status: [
+        ("eskom".to_owned(), status("National", "3")),
+        ("capetown".to_owned(), status("Cape Town", "2")),
+      ]
+      .into(),
+    }
+  }
+
+  #[test]
+  fn built_in_defaults() {
+    let resolver = StatusResolver::default();
+    let status = status();
+    assert_eq!(
+      resolver
+        .resolve(&status, "City of Cape Town", None)
+        .unwrap()
+        .stage,
+      "2"
+    );
+    assert_eq!(
+      resolver
+        .resolve(&status, "", Some("capetown-5-brooklyn"))
+        .unwrap()
+        .stage,
+      "2"
+    );
+    assert_eq!(
+      resolver
+        .resolve(&status, "City of Tshwane", Some("tshwane-6-brooklyn"))
+        .unwrap()
+        .stage,
+      "3"
+    );
+  }
+
+  #[test]
+  fn falls_back_to_national_when_override_is_missing() {
+    let resolver = StatusResolver::empty().with_region("City of Johannesburg", "joburg");
+    assert_eq!(resolver.key_for("city of johannesburg", None), "joburg");
+    assert_eq!(
+      resolver
+        .resolve(&status(), "City of Johannesburg", None)
+        .unwrap()
+        .stage,
+      "3"
+    );
+    assert_eq!(resolver.key_for("City of Cape Town", None), "eskom");
+  }
+}
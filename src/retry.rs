@@ -0,0 +1,280 @@
+//! Automatic retries with exponential backoff and jitter for transient failures.
+//!
+//! A [`RetryPolicy`] can be added to any of the clients. `400` (Bad Request) and `403` (Forbidden)
+//! responses are never retried.
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! use eskom_se_push_api::{
+//!   retry::{RetryOn, RetryPolicyBuilder},
+//!   ureq_client::UreqClient,
+//! };
+//!
+//! let policy = RetryPolicyBuilder::default()
+//!   .max_attempts(5u32)
+//!   .initial_backoff(Duration::from_secs(1))
+//!   .retry_on(vec![RetryOn::Timeout, RetryOn::NoInternet, RetryOn::ServerError])
+//!   .build()
+//!   .unwrap();
+//! let api = UreqClient::new_with_env(None).with_retry_policy(policy);
+//! ```
+
+use std::time::Duration;
+
+#[cfg(any(feature = "async", doc))]
+use async_trait::async_trait;
+use bytes::Bytes;
+use derive_builder::Builder;
+use http::{header::RETRY_AFTER, StatusCode};
+
+#[cfg(any(feature = "async", doc))]
+use crate::transport::AsyncTransport;
+use crate::{errors::HttpError, transport::Transport};
+
+/// The transient failures that can be retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RetryOn {
+  /// [`HttpError::Timeout`]
+  Timeout,
+  /// [`HttpError::NoInternet`]
+  NoInternet,
+  /// A 5xx response ([`APIError::ServerError`](crate::errors::APIError::ServerError))
+  ServerError,
+  /// A 429 response ([`APIError::TooManyRequests`](crate::errors::APIError::TooManyRequests)).
+  /// The `Retry-After` header is used as the delay if it is set.
+  TooManyRequests,
+}
+
+/// How failed requests are retried
+#[derive(Debug, Clone, Builder)]
+#[builder(setter(into))]
+pub struct RetryPolicy {
+  /// The total number of attempts including the first request. Default is `3`
+  #[builder(default = "3")]
+  max_attempts: u32,
+  /// The delay before the first retry. Default is `500ms`
+  #[builder(default = "Duration::from_millis(500)")]
+  initial_backoff: Duration,
+  /// The longest delay between attempts. Default is `30s`.
+  /// `Note`: A `Retry-After` longer than this isn't waited for and the 429 is returned instead.
+  #[builder(default = "Duration::from_secs(30)")]
+  max_backoff: Duration,
+  /// The backoff is multiplied by this after every attempt. Default is `2`
+  #[builder(default = "2.0")]
+  multiplier: f64,
+  /// Randomises the delay between half and the full backoff so clients don't retry in lockstep. Default is `true`
+  #[builder(default = "true")]
+  jitter: bool,
+  /// The failures that are retried. Default is timeouts, no internet and server errors
+  #[builder(default = "vec![RetryOn::Timeout, RetryOn::NoInternet, RetryOn::ServerError]")]
+  retry_on: Vec<RetryOn>,
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    RetryPolicyBuilder::default().build().unwrap()
+  }
+}
+
+impl RetryPolicy {
+  /// A policy that never retries. This is what the clients use by default
+  pub fn never() -> Self {
+    RetryPolicyBuilder::default()
+      .max_attempts(1u32)
+      .build()
+      .unwrap()
+  }
+
+  /// The total number of attempts including the first request
+  pub fn max_attempts(&self) -> u32 {
+    self.max_attempts
+  }
+
+  /// The delay before the given retry (`1` is the first retry) without any jitter
+  pub fn backoff(&self, retry: u32) -> Duration {
+    let factor = self.multiplier.powi(retry.saturating_sub(1) as i32);
+    self
+      .initial_backoff
+      .mul_f64(factor.max(0.))
+      .min(self.max_backoff)
+  }
+
+  /// Returns how long to wait before retrying the request or `None` if it shouldn't be retried.
+  /// `attempt` is the number of attempts made so far.
+  pub fn retry_delay(
+    &self,
+    attempt: u32,
+    outcome: &Result<http::Response<Bytes>, HttpError>,
+  ) -> Option<Duration> {
+    if attempt >= self.max_attempts {
+      return None;
+    }
+    let retry_on = match outcome {
+      Err(HttpError::Timeout { .. }) => RetryOn::Timeout,
+      Err(HttpError::NoInternet { .. }) => RetryOn::NoInternet,
+      Ok(resp) if resp.status() == StatusCode::TOO_MANY_REQUESTS => RetryOn::TooManyRequests,
+      Ok(resp) if resp.status().is_server_error() => RetryOn::ServerError,
+      _ => return None,
+    };
+    if !self.retry_on.contains(&retry_on) {
+      return None;
+    }
+    if let Ok(resp) = outcome {
+      if let Some(retry_after) = retry_after(resp) {
+        return (retry_after <= self.max_backoff).then_some(retry_after);
+      }
+    }
+    let backoff = self.backoff(attempt);
+    if self.jitter {
+      let half = backoff / 2;
+      Some(half + half.mul_f64(fastrand::f64()))
+    } else {
+      Some(backoff)
+    }
+  }
+}
+
+/// Parses the `Retry-After` header which is either in seconds or a HTTP date
+fn retry_after(response: &http::Response<Bytes>) -> Option<Duration> {
+  let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+  if let Ok(seconds) = value.parse::<u64>() {
+    return Some(Duration::from_secs(seconds));
+  }
+  let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+  Some(
+    (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+      .to_std()
+      .unwrap_or_default(),
+  )
+}
+
+/// Copies the request so it can be sent again
+fn clone_request(request: &http::Request<Bytes>) -> http::Request<Bytes> {
+  let mut clone = http::Request::new(request.body().clone());
+  *clone.method_mut() = request.method().clone();
+  *clone.uri_mut() = request.uri().clone();
+  *clone.version_mut() = request.version();
+  *clone.headers_mut() = request.headers().clone();
+  clone
+}
+
+/// A transport that retries the requests of the inner transport using the [`RetryPolicy`]
+pub struct Retry<'a, T> {
+  transport: T,
+  policy: &'a RetryPolicy,
+}
+
+impl<'a, T> Retry<'a, T> {
+  pub fn new(transport: T, policy: &'a RetryPolicy) -> Self {
+    Retry { transport, policy }
+  }
+}
+
+impl<T: Transport> Transport for Retry<'_, T> {
+  fn send(&self, request: http::Request<Bytes>) -> Result<http::Response<Bytes>, HttpError> {
+    let mut attempt = 1;
+    loop {
+      let outcome = self.transport.send(clone_request(&request));
+      match self.policy.retry_delay(attempt, &outcome) {
+        Some(delay) => std::thread::sleep(delay),
+        None => return outcome,
+      }
+      attempt += 1;
+    }
+  }
+}
+
+#[cfg(any(feature = "async", doc))]
+#[async_trait]
+impl<T: AsyncTransport + Sync> AsyncTransport for Retry<'_, T> {
+  async fn send(&self, request: http::Request<Bytes>) -> Result<http::Response<Bytes>, HttpError> {
+    let mut attempt = 1;
+    loop {
+      let outcome = self.transport.send(clone_request(&request)).await;
+      match self.policy.retry_delay(attempt, &outcome) {
+        Some(delay) => tokio::time::sleep(delay).await,
+        None => return outcome,
+      }
+      attempt += 1;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn response(status: u16, retry_after: Option<&str>) -> Result<http::Response<Bytes>, HttpError> {
+    let mut builder = http::Response::builder().status(status);
+    if let Some(value) = retry_after {
+      builder = builder.header(RETRY_AFTER, value);
+    }
+    Ok(builder.body(Bytes::new()).unwrap())
+  }
+
+  fn timeout() -> HttpError {
+    HttpError::Timeout {
+      context: Default::default(),
+      source: None,
+    }
+  }
+
+  fn policy() -> RetryPolicy {
+    RetryPolicyBuilder::default()
+      .jitter(false)
+      .retry_on(vec![
+        RetryOn::Timeout,
+        RetryOn::ServerError,
+        RetryOn::TooManyRequests,
+      ])
+      .build()
+      .unwrap()
+  }
+
+  #[test]
+  fn backoff_is_exponential_and_capped() {
+    let policy = RetryPolicyBuilder::default()
+      .initial_backoff(Duration::from_secs(1))
+      .max_backoff(Duration::from_secs(5))
+      .build()
+      .unwrap();
+    assert_eq!(policy.backoff(1), Duration::from_secs(1));
+    assert_eq!(policy.backoff(2), Duration::from_secs(2));
+    assert_eq!(policy.backoff(3), Duration::from_secs(4));
+    assert_eq!(policy.backoff(4), Duration::from_secs(5));
+  }
+
+  #[test]
+  fn retries_transient_failures_only() {
+    let policy = policy();
+    assert!(policy.retry_delay(1, &Err(timeout())).is_some());
+    assert!(policy.retry_delay(1, &response(503, None)).is_some());
+    assert!(policy
+      .retry_delay(
+        1,
+        &Err(HttpError::NoInternet {
+          context: Default::default(),
+          source: None
+        })
+      )
+      .is_none());
+    assert!(policy.retry_delay(1, &response(400, None)).is_none());
+    assert!(policy.retry_delay(1, &response(403, None)).is_none());
+    assert!(policy.retry_delay(1, &response(200, None)).is_none());
+    assert!(policy.retry_delay(3, &response(503, None)).is_none());
+  }
+
+  #[test]
+  fn honours_retry_after() {
+    let policy = policy();
+    assert_eq!(
+      policy.retry_delay(1, &response(429, Some("7"))),
+      Some(Duration::from_secs(7))
+    );
+    assert_eq!(policy.retry_delay(1, &response(429, Some("3600"))), None);
+    assert_eq!(
+      policy.retry_delay(1, &response(429, Some("Wed, 21 Oct 2015 07:28:00 GMT"))),
+      Some(Duration::ZERO)
+    );
+  }
+}
@@ -0,0 +1,385 @@
+//! A typed view of the raw loadshedding schedule of an area.
+//!
+//! The API returns the schedule of each day as display strings eg `20:00-22:30`.
+//! [`Day::parse`](crate::area_info::Day::parse) turns a day into a [`DaySchedule`] with the dates, weekdays
+//! and [`TimeRange`]s parsed.
+//! ```rust
+//! use chrono::{NaiveTime, Weekday};
+//! use eskom_se_push_api::area_info::Day;
+//!
+//! let day = Day {
+//!   date: "2022-08-08".to_owned(),
+//!   name: "Monday".to_owned(),
+//!   stages: vec![vec!["22:00-00:30".to_owned()]],
+//! };
+//! let schedule = day.parse().unwrap();
+//! assert_eq!(schedule.weekday, Weekday::Mon);
+//! let range = schedule.stages[0][0];
+//! assert_eq!(range.start, NaiveTime::from_hms_opt(22, 0, 0).unwrap());
+//! assert!(range.crosses_midnight());
+//! ```
+//!
+//! The schedule can also be projected into the concrete outage windows for a stage (or a [`StageTimeline`])
+//! to see what would happen if the stage changes before it is announced.
+//! ```rust,no_run
+//! use eskom_se_push_api::{status::Stage, ureq_client::UreqClient};
+//!
+//! let api = UreqClient::new_with_env(None);
+//! let info = api.get_area_info("tshwane-6-brooklyn").unwrap();
+//! // What if it goes to Stage 6 for the next 3 days?
+//! for window in info.schedule.project_stage(&Stage::Stage6, 3).unwrap() {
+//!   println!("{} until {}", window.start, window.end);
+//! }
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use chrono::{
+  DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+
+use crate::{area_info::Day, errors::ScheduleError, status::Stage};
+
+/// The format of the times in the schedule eg `20:00`
+const TIME_FORMAT: &str = "%H:%M";
+
+/// The highest stage most regions have a schedule for.
+/// Higher stages use this schedule if the region doesn't have one for them
+const FALLBACK_STAGE: usize = 4;
+
+/// The offset of South African Standard Time (`+02:00`) which the schedule times are in
+pub fn sast() -> FixedOffset {
+  FixedOffset::east_opt(2 * 60 * 60).unwrap()
+}
+
+/// A period of loadshedding on a day eg `20:00-22:30`.
+/// The `end` is before (or equal to) the `start` if the period continues into the next day eg `22:00-00:30`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimeRange {
+  pub start: NaiveTime,
+  pub end: NaiveTime,
+}
+
+impl TimeRange {
+  pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+    TimeRange { start, end }
+  }
+
+  /// Whether the period ends on the next day
+  pub fn crosses_midnight(&self) -> bool {
+    self.end <= self.start
+  }
+
+  /// How long the period lasts
+  pub fn duration(&self) -> Duration {
+    let duration = self.end - self.start;
+    if self.crosses_midnight() {
+      duration + Duration::days(1)
+    } else {
+      duration
+    }
+  }
+
+  /// The start and end of the period when it starts on `date`
+  pub fn on(&self, date: NaiveDate) -> (NaiveDateTime, NaiveDateTime) {
+    let start = date.and_time(self.start);
+    (start, start + self.duration())
+  }
+}
+
+impl FromStr for TimeRange {
+  type Err = ScheduleError;
+
+  fn from_str(range: &str) -> Result<Self, Self::Err> {
+    let invalid = || ScheduleError::InvalidTimeRange(range.to_owned());
+    let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+    let parse =
+      |time: &str| NaiveTime::parse_from_str(time.trim(), TIME_FORMAT).map_err(|_| invalid());
+    Ok(TimeRange::new(parse(start)?, parse(end)?))
+  }
+}
+
+impl fmt::Display for TimeRange {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}-{}",
+      self.start.format(TIME_FORMAT),
+      self.end.format(TIME_FORMAT)
+    )
+  }
+}
+
+/// The parsed schedule of a [`Day`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DaySchedule {
+  /// Date the stages are relevant to
+  pub date: NaiveDate,
+  /// Day of the week
+  pub weekday: Weekday,
+  /// The periods of loadshedding per stage.
+  /// Index 0 refers to `Stage 1`, index 1 is `Stage 2` and so on and so forth.
+  pub stages: Vec<Vec<TimeRange>>,
+}
+
+impl DaySchedule {
+  /// The periods of loadshedding for the stage.
+  /// Stage 5 upwards falls back to the `Stage 4` schedule if the region doesn't have a schedule for it.
+  /// Returns an empty list for no loadshedding or a [`Stage::Unknown`]
+  pub fn stage(&self, stage: &Stage) -> &[TimeRange] {
+    let Some(number) = stage.number().filter(|number| *number > 0) else {
+      return &[];
+    };
+    let index = number as usize - 1;
+    self
+      .stages
+      .get(index)
+      .or_else(|| {
+        (index >= FALLBACK_STAGE)
+          .then(|| self.stages.get(FALLBACK_STAGE - 1))
+          .flatten()
+      })
+      .map_or(&[], Vec::as_slice)
+  }
+
+  /// The outage windows of the stage on this day in South African time
+  pub fn windows(&self, stage: &Stage) -> Vec<OutageWindow> {
+    self
+      .stage(stage)
+      .iter()
+      .map(|range| {
+        let (start, end) = range.on(self.date);
+        OutageWindow {
+          start: local(start),
+          end: local(end),
+          stage: stage.clone(),
+        }
+      })
+      .collect()
+  }
+}
+
+impl TryFrom<&Day> for DaySchedule {
+  type Error = ScheduleError;
+
+  fn try_from(day: &Day) -> Result<Self, Self::Error> {
+    let date = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d")
+      .map_err(|_| ScheduleError::InvalidDate(day.date.clone()))?;
+    let weekday = day
+      .name
+      .parse()
+      .map_err(|_| ScheduleError::InvalidWeekday(day.name.clone()))?;
+    let stages = day
+      .stages
+      .iter()
+      .map(|ranges| ranges.iter().map(|range| range.parse()).collect())
+      .collect::<Result<_, _>>()?;
+    Ok(DaySchedule {
+      date,
+      weekday,
+      stages,
+    })
+  }
+}
+
+/// Converts a time in the schedule to a South African time
+fn local(time: NaiveDateTime) -> DateTime<FixedOffset> {
+  sast().from_local_datetime(&time).unwrap()
+}
+
+/// A concrete period the power is off in an area
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutageWindow {
+  pub start: DateTime<FixedOffset>,
+  pub end: DateTime<FixedOffset>,
+  /// The stage that causes the outage
+  pub stage: Stage,
+}
+
+impl OutageWindow {
+  /// How long the power is off
+  pub fn duration(&self) -> Duration {
+    self.end - self.start
+  }
+
+  /// Whether the power is off at the given time. The start is inclusive and the end exclusive
+  pub fn contains<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> bool {
+    self.start <= *at && self.end > *at
+  }
+}
+
+/// The stage over time eg `Stage 2` until 16:00 then `Stage 4`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageTimeline {
+  /// The stage and when it starts, sorted by when it starts
+  changes: Vec<(DateTime<Utc>, Stage)>,
+}
+
+impl StageTimeline {
+  /// A timeline that stays at the stage
+  pub fn constant(stage: Stage) -> Self {
+    StageTimeline {
+      changes: vec![(DateTime::<Utc>::MIN_UTC, stage)],
+    }
+  }
+
+  /// Changes to the stage at the given time
+  pub fn then<Tz: TimeZone>(mut self, at: &DateTime<Tz>, stage: Stage) -> Self {
+    let at = at.with_timezone(&Utc);
+    let index = self.changes.partition_point(|(start, _)| *start <= at);
+    self.changes.insert(index, (at, stage));
+    self
+  }
+
+  /// The stage at the given time
+  pub fn stage_at<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> &Stage {
+    let index = self.changes.partition_point(|(start, _)| *start <= *at);
+    &self.changes[index.saturating_sub(1)].1
+  }
+
+  /// Each stage with when it starts and when it ends (`None` if it doesn't end)
+  pub fn periods(&self) -> impl Iterator<Item = (DateTime<Utc>, Option<DateTime<Utc>>, &Stage)> {
+    self.changes.iter().enumerate().map(|(i, (start, stage))| {
+      let end = self.changes.get(i + 1).map(|(end, _)| *end);
+      (*start, end, stage)
+    })
+  }
+}
+
+/// Projects the days into the outage windows of the stages in the timeline.
+/// The windows are clipped to when their stage is in effect and overlapping windows of the same stage are merged.
+pub fn project(days: &[DaySchedule], timeline: &StageTimeline) -> Vec<OutageWindow> {
+  let mut windows = Vec::new();
+  for (from, until, stage) in timeline.periods() {
+    for day in days {
+      for mut window in day.windows(stage) {
+        window.start = window.start.max(from.with_timezone(&sast()));
+        if let Some(until) = until {
+          window.end = window.end.min(until.with_timezone(&sast()));
+        }
+        if window.start < window.end {
+          windows.push(window);
+        }
+      }
+    }
+  }
+  windows.sort_by_key(|window| window.start);
+  let mut merged: Vec<OutageWindow> = Vec::with_capacity(windows.len());
+  for window in windows {
+    match merged.last_mut() {
+      Some(last) if last.stage == window.stage && window.start <= last.end => {
+        last.end = last.end.max(window.end);
+      }
+      _ => merged.push(window),
+    }
+  }
+  merged
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn time(hour: u32, min: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, min, 0).unwrap()
+  }
+
+  #[test]
+  fn parses_time_ranges() {
+    let range: TimeRange = "20:00-22:30".parse().unwrap();
+    assert_eq!(range, TimeRange::new(time(20, 0), time(22, 30)));
+    assert!(!range.crosses_midnight());
+    assert_eq!(range.duration(), Duration::minutes(150));
+    assert_eq!(range.to_string(), "20:00-22:30");
+    assert!("20:00".parse::<TimeRange>().is_err());
+    assert!("25:00-26:00".parse::<TimeRange>().is_err());
+  }
+
+  #[test]
+  fn handles_ranges_crossing_midnight() {
+    let range: TimeRange = "22:00-00:30".parse().unwrap();
+    assert!(range.crosses_midnight());
+    assert_eq!(range.duration(), Duration::minutes(150));
+    let date = NaiveDate::from_ymd_opt(2022, 8, 8).unwrap();
+    let (start, end) = range.on(date);
+    assert_eq!(start, date.and_time(time(22, 0)));
+    assert_eq!(end, date.succ_opt().unwrap().and_time(time(0, 30)));
+  }
+
+  fn days() -> Vec<DaySchedule> {
+    let day = |date: &str, name: &str| Day {
+      date: date.to_owned(),
+      name: name.to_owned(),
+      stages: vec![
+        vec![],
+        vec!["20:00-22:30".to_owned()],
+        vec!["20:00-22:30".to_owned()],
+        vec!["12:00-14:30".to_owned(), "22:00-00:30".to_owned()],
+      ],
+    };
+    vec![
+      day("2022-08-08", "Monday").parse().unwrap(),
+      day("2022-08-09", "Tuesday").parse().unwrap(),
+    ]
+  }
+
+  fn sast_time(time: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(time).unwrap()
+  }
+
+  #[test]
+  fn higher_stages_fall_back_to_stage_4() {
+    let days = days();
+    assert_eq!(days[0].stage(&Stage::Stage6), days[0].stage(&Stage::Stage4));
+    assert!(days[0].stage(&Stage::Stage1).is_empty());
+    assert!(days[0].stage(&Stage::NoLoadShedding).is_empty());
+    assert!(days[0]
+      .stage(&Stage::Unknown("Load reduction".to_owned()))
+      .is_empty());
+  }
+
+  #[test]
+  fn projects_a_timeline() {
+    let timeline = StageTimeline::constant(Stage::Stage2)
+      .then(&sast_time("2022-08-09T00:00:00+02:00"), Stage::Stage6);
+    let windows = project(&days(), &timeline);
+    let expected = [
+      (
+        "2022-08-08T20:00:00+02:00",
+        "2022-08-08T22:30:00+02:00",
+        Stage::Stage2,
+      ),
+      (
+        "2022-08-09T00:00:00+02:00",
+        "2022-08-09T00:30:00+02:00",
+        Stage::Stage6,
+      ),
+      (
+        "2022-08-09T12:00:00+02:00",
+        "2022-08-09T14:30:00+02:00",
+        Stage::Stage6,
+      ),
+      (
+        "2022-08-09T22:00:00+02:00",
+        "2022-08-10T00:30:00+02:00",
+        Stage::Stage6,
+      ),
+    ];
+    assert_eq!(windows.len(), expected.len());
+    for (window, (start, end, stage)) in windows.iter().zip(expected) {
+      assert_eq!(window.start, sast_time(start));
+      assert_eq!(window.end, sast_time(end));
+      assert_eq!(window.stage, stage);
+    }
+  }
+
+  #[test]
+  fn merges_overlapping_windows() {
+    let mut days = days();
+    days[1].stages[3] = vec!["00:00-02:30".parse().unwrap()];
+    let windows = project(&days, &StageTimeline::constant(Stage::Stage4));
+    let last = windows.last().unwrap();
+    assert_eq!(last.start, sast_time("2022-08-08T22:00:00+02:00"));
+    assert_eq!(last.end, sast_time("2022-08-09T02:30:00+02:00"));
+  }
+}
@@ -6,11 +6,14 @@ use derive_builder::Builder;
 use serde::Deserialize;
 use serde::Serialize;
 
-use crate::traits::Endpoint;
+use crate::schedule::StageTimeline;
 #[cfg(any(feature = "async", doc))]
 use crate::traits::EndpointAsync;
+use crate::traits::{endpoint_url, Endpoint};
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub enum Stage {
+  #[default]
   NoLoadShedding,
   Stage1,
   Stage2,
@@ -23,6 +26,60 @@ pub enum Stage {
   /// Able to check any stage (Also future proofing). String should be a whole number.
   /// "0" is no loadshedding. "1" is Stage 1 etc.
   Stage(String),
+  /// A note that couldn't be parsed as a stage. Holds the raw note eg `Load reduction`
+  Unknown(String),
+}
+
+impl Stage {
+  /// Parses the note of an area event eg `Stage 2`.
+  /// Falls back to [`Stage::Unknown`] if the note doesn't contain a stage
+  pub fn from_note(note: &str) -> Self {
+    let lower = note.to_lowercase();
+    if lower.contains("no load") || lower.contains("not load") {
+      return Self::NoLoadShedding;
+    }
+    lower
+      .find("stage")
+      .map(|i| {
+        lower[i + "stage".len()..]
+          .trim_start()
+          .chars()
+          .take_while(|c| c.is_ascii_digit())
+          .collect::<String>()
+      })
+      .filter(|number| !number.is_empty())
+      .map_or_else(|| Self::Unknown(note.to_owned()), Self::from)
+  }
+
+  /// The stage as a number eg `2` for [`Stage::Stage2`] and `0` for [`Stage::NoLoadShedding`].
+  /// Returns `None` for [`Stage::Unknown`] or if [`Stage::Stage`] isn't a whole number
+  pub fn number(&self) -> Option<u32> {
+    match self {
+      Stage::NoLoadShedding => Some(0),
+      Stage::Stage1 => Some(1),
+      Stage::Stage2 => Some(2),
+      Stage::Stage3 => Some(3),
+      Stage::Stage4 => Some(4),
+      Stage::Stage5 => Some(5),
+      Stage::Stage6 => Some(6),
+      Stage::Stage7 => Some(7),
+      Stage::Stage8 => Some(8),
+      Stage::Stage(stage) => stage.parse().ok(),
+      Stage::Unknown(_) => None,
+    }
+  }
+}
+
+impl std::fmt::Display for Stage {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match (self, self.number()) {
+      (Stage::NoLoadShedding, _) => write!(f, "No Load Shedding"),
+      (Stage::Unknown(note), _) => write!(f, "{}", note),
+      (_, Some(number)) => write!(f, "Stage {}", number),
+      (Stage::Stage(stage), None) => write!(f, "Stage {}", stage),
+      _ => Ok(()),
+    }
+  }
 }
 
 impl PartialEq<String> for Stage {
@@ -38,6 +95,7 @@ impl PartialEq<String> for Stage {
       Stage::Stage7 => "7" == other,
       Stage::Stage8 => "8" == other,
       Stage::Stage(stage) => stage == other,
+      Stage::Unknown(_) => false,
     }
   }
 }
@@ -61,17 +119,18 @@ impl From<String> for Stage {
 
 #[derive(Default, Builder, Debug)]
 #[builder(setter(into))]
-pub struct EskomStatusUrl {}
+pub struct EskomStatusUrl {
+  /// Overrides the base URL of the API eg a staging proxy or a local mock server.
+  /// Defaults to [`BASE_URL`](crate::constants::BASE_URL) if not set
+  #[builder(default, setter(into, strip_option))]
+  base_url: Option<String>,
+}
 
 impl Endpoint for EskomStatusUrl {
   type Output = EskomStatus;
 
   fn endpoint(&self) -> std::borrow::Cow<'static, str> {
-    std::borrow::Cow::Borrowed("https://developer.sepush.co.za/business/2.0/status")
-  }
-
-  fn url(&self) -> Result<url::Url, crate::errors::HttpError> {
-    Ok(url::Url::parse(&self.endpoint()).unwrap())
+    endpoint_url(self.base_url.as_deref(), "status")
   }
 }
 
@@ -123,6 +182,14 @@ impl LoadsheddingStatus {
   pub fn get_stage(&self) -> Stage {
     self.stage.clone().into()
   }
+
+  /// The current stage followed by the announced stage changes
+  pub fn timeline(&self) -> StageTimeline {
+    self.next_stages.iter().fold(
+      StageTimeline::constant(self.get_stage()),
+      |timeline, next| timeline.then(&next.stage_start_timestamp, next.get_stage()),
+    )
+  }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -142,3 +209,30 @@ impl NextStage {
     self.stage.clone().into()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_event_notes() {
+    assert_eq!(Stage::from_note("Stage 2"), Stage::Stage2);
+    assert_eq!(Stage::from_note("stage 8"), Stage::Stage8);
+    assert_eq!(Stage::from_note("Stage 10"), Stage::Stage("10".to_owned()));
+    assert_eq!(Stage::from_note("No Load Shedding"), Stage::NoLoadShedding);
+    assert_eq!(
+      Stage::from_note("Load reduction"),
+      Stage::Unknown("Load reduction".to_owned())
+    );
+  }
+
+  #[test]
+  fn displays_as_note() {
+    assert_eq!(Stage::Stage4.to_string(), "Stage 4");
+    assert_eq!(Stage::from_note("Stage 10").to_string(), "Stage 10");
+    assert_eq!(
+      Stage::Unknown("Load reduction".to_owned()).to_string(),
+      "Load reduction"
+    );
+  }
+}
@@ -3,12 +3,52 @@ use std::borrow::Cow;
 #[cfg(any(feature = "async", doc))]
 use async_trait::async_trait;
 
+use bytes::Bytes;
 use serde::de::DeserializeOwned;
 
-#[cfg(any(feature = "ureq", doc))]
-use crate::ureq_client::handle_ureq_response;
+#[cfg(any(feature = "async", doc))]
+use crate::transport::AsyncTransport;
+use crate::{
+  allowance::AllowanceCheck,
+  area_info::{AreaInfo, TestMode},
+  area_nearby::AreaNearby,
+  area_search::AreaSearch,
+  constants::{BASE_URL, TOKEN_KEY},
+  errors::HttpError,
+  status::EskomStatus,
+  topics_nearby::TopicsNearby,
+  transport::{handle_response_with_context, request_context, Transport},
+};
+
+/// Joins the `path` of an endpoint onto the `base_url`, falling back to [`BASE_URL`] if no base URL is set
+pub(crate) fn endpoint_url(base_url: Option<&str>, path: &str) -> Cow<'static, str> {
+  let base = base_url.unwrap_or(BASE_URL).trim_end_matches('/');
+  Cow::Owned(format!("{}/{}", base, path))
+}
+
+/// Builds the request for the endpoint. The token is left out for clients that add it to their default headers
+fn build_request<E: Endpoint + ?Sized>(
+  endpoint: &E,
+  token: Option<&str>,
+) -> Result<http::Request<Bytes>, HttpError> {
+  let url_endpoint = endpoint.url()?;
+  let mut builder = http::Request::builder()
+    .method(endpoint.method())
+    .uri(url_endpoint.as_str());
+  if let Some(token) = token {
+    builder = builder.header(TOKEN_KEY, token);
+  }
+  Ok(builder.body(Bytes::new())?)
+}
 
-use crate::errors::HttpError;
+/// Sends the request with the `transport` and handles the response
+fn send<O: DeserializeOwned, T: Transport + ?Sized>(
+  transport: &T,
+  request: http::Request<Bytes>,
+) -> Result<O, HttpError> {
+  let context = request_context(&request);
+  handle_response_with_context(transport.send(request)?, context)
+}
 
 pub trait Endpoint {
   type Output: DeserializeOwned;
@@ -21,9 +61,35 @@ pub trait Endpoint {
   /// Returns the endpoint Url BUT it won't have any queries attached to it
   fn endpoint(&self) -> Cow<'static, str>;
 
-  /// Returns the built URL for this endpoint
+  /// Returns the query parameters of the endpoint eg `[("id", "tshwane-6-brooklyn")]`.
+  /// Returns an error if a required parameter isn't set or is invalid. `NOTE` Default is no parameters
+  fn query_pairs(&self) -> Result<Vec<(&'static str, String)>, HttpError> {
+    Ok(Vec::new())
+  }
+
+  /// Returns the built URL for this endpoint with the query parameters percent-encoded
   fn url(&self) -> Result<url::Url, HttpError> {
-    Ok(url::Url::parse(&self.endpoint()).unwrap())
+    let mut url = url::Url::parse(&self.endpoint())?;
+    let pairs = self.query_pairs()?;
+    if !pairs.is_empty() {
+      url.query_pairs_mut().extend_pairs(pairs);
+    }
+    Ok(url)
+  }
+
+  /// Returns the request for this endpoint with the token added to the headers
+  fn request(&self, token: &str) -> Result<http::Request<Bytes>, HttpError> {
+    build_request(self, Some(token))
+  }
+
+  /// Uses the `transport` to make the API call and handle the response.
+  /// This is used by all the clients so any http library can be used by implementing [`Transport`]
+  fn query<T: Transport + ?Sized>(
+    &self,
+    transport: &T,
+    token: &str,
+  ) -> Result<Self::Output, HttpError> {
+    send(transport, self.request(token)?)
   }
 
   #[cfg(any(feature = "ureq", doc))]
@@ -31,22 +97,14 @@ pub trait Endpoint {
   /// The assumption is made that the token is part of the default headers
   /// Requires the `ureq` feature to be enabled
   fn ureq_client(&self, client: &ureq::Agent) -> Result<Self::Output, HttpError> {
-    let url_endpoint = self.url()?;
-    handle_ureq_response(client.request(self.method(), url_endpoint.as_str()).call())
+    send(client, build_request(self, None)?)
   }
 
   #[cfg(any(feature = "ureq", doc))]
   /// Creates a `ureq` client to make the API call and handle the response
   /// Requires the `ureq` feature to be enabled
   fn ureq(&self, token: &str) -> Result<Self::Output, HttpError> {
-    use crate::constants::TOKEN_KEY;
-
-    let url_endpoint = self.url()?;
-    handle_ureq_response(
-      ureq::request(self.method(), url_endpoint.as_str())
-        .set(TOKEN_KEY, token)
-        .call(),
-    )
+    self.query(&ureq::agent(), token)
   }
 
   #[cfg(any(all(feature = "reqwest", feature = "sync"), doc))]
@@ -54,36 +112,32 @@ pub trait Endpoint {
   /// The assumption is made that the token is part of the default headers
   /// Requires the `reqwest` and `sync` features to be enabled
   fn reqwest_client(&self, client: &reqwest::blocking::Client) -> Result<Self::Output, HttpError> {
-    let url_endpoint = self.url()?;
-    crate::reqwest_blocking_client::handle_reqwest_response_blocking::<Self::Output>(
-      client.get(url_endpoint.as_str()).send(),
-    )
+    send(client, build_request(self, None)?)
   }
 
   #[cfg(any(all(feature = "reqwest", feature = "sync"), doc))]
   /// Creates a `reqwest::blocking` client to make the API call and handle the response
   /// Requires the `reqwest` and `sync` features to be enabled
   fn reqwest(&self, token: &str) -> Result<Self::Output, HttpError> {
-    use http::header;
-
-    use crate::constants::TOKEN_KEY;
-
-    let url_endpoint = self.url()?;
-    let mut headers = header::HeaderMap::new();
-    headers.insert(TOKEN_KEY, header::HeaderValue::from_str(token).unwrap());
-    let client = reqwest::blocking::ClientBuilder::new()
-      .default_headers(headers)
-      .build()
-      .unwrap();
-    crate::reqwest_blocking_client::handle_reqwest_response_blocking::<Self::Output>(
-      client.get(url_endpoint.as_str()).send(),
-    )
+    self.query(&reqwest::blocking::Client::new(), token)
   }
 }
 
-#[cfg(any(all(feature = "reqwest", feature = "async"), doc))]
+#[cfg(any(feature = "async", doc))]
 #[async_trait]
 pub trait EndpointAsync: Endpoint {
+  /// Uses the async `transport` to make the API call and handle the response.
+  /// This is used by the async client so any http library can be used by implementing [`AsyncTransport`]
+  async fn query_async<T: AsyncTransport + Sync + ?Sized>(
+    &self,
+    transport: &T,
+    token: &str,
+  ) -> Result<Self::Output, HttpError> {
+    let request = self.request(token)?;
+    let context = request_context(&request);
+    handle_response_with_context(transport.send(request).await?, context)
+  }
+
   #[cfg(any(all(feature = "reqwest", feature = "async"), doc))]
   /// Uses an async `reqwest` client to make the API call and handle the response.
   /// The assumption is made that the token is part of the default headers
@@ -92,31 +146,154 @@ pub trait EndpointAsync: Endpoint {
     &self,
     client: &reqwest::Client,
   ) -> Result<Self::Output, HttpError> {
-    let url_endpoint = self.url()?;
-    crate::reqwest_async_client::handle_reqwest_response::<Self::Output>(
-      client.get(url_endpoint.as_str()).send().await,
-    )
-    .await
+    let request = build_request(self, None)?;
+    let context = request_context(&request);
+    handle_response_with_context(client.send(request).await?, context)
   }
 
   #[cfg(any(all(feature = "reqwest", feature = "async"), doc))]
   /// Creates an async `reqwest` client to make the API call and handle the response
   /// Requires the `reqwest` and `async` features to be enabled
   async fn reqwest_async(&self, token: &str) -> Result<Self::Output, HttpError> {
-    let url_endpoint = self.url()?;
-
-    let mut headers = http::header::HeaderMap::new();
-    headers.insert(
-      crate::constants::TOKEN_KEY,
-      http::header::HeaderValue::from_str(token).unwrap(),
-    );
-    let client = reqwest::ClientBuilder::new()
-      .default_headers(headers)
-      .build()
-      .unwrap();
-    crate::reqwest_async_client::handle_reqwest_response::<Self::Output>(
-      client.get(url_endpoint.as_str()).send().await,
-    )
-    .await
+    self.query_async(&reqwest::Client::new(), token).await
+  }
+}
+
+/// The API calls available on every blocking client.
+/// Allows code to be generic over the http backend and swap in a fake client in tests.
+pub trait EskomClient {
+  /// The current and next loadshedding statuses for South Africa and (Optional) municipal overrides
+  fn get_load_shedding_status(&self) -> Result<EskomStatus, HttpError>;
+
+  /// Obtain the `area_id` from Area Find or Area Search and use with this request. This single request has everything you need to monitor upcoming loadshedding events for the chosen suburb.
+  fn get_area_info(&self, area_id: &str) -> Result<AreaInfo, HttpError>;
+
+  /// Same as `get_area_info` but returns synthetic events for the test mode without using the quota.
+  /// Useful for building UIs against realistic "loadshedding now" and "loadshedding soon" responses.
+  /// `NOTE` Default returns an error so clients that can't call the API (eg fakes) don't need to implement it
+  fn get_area_info_with_test(&self, area_id: &str, test: TestMode) -> Result<AreaInfo, HttpError> {
+    let _ = (area_id, test);
+    Err(HttpError::UnknownError(
+      "Test mode isn't supported by this client".to_owned(),
+    ))
+  }
+
+  /// Find areas based on GPS coordinates (latitude and longitude).
+  fn areas_nearby(&self, lat: f32, long: f32) -> Result<AreaNearby, HttpError>;
+
+  /// Search area based on text
+  fn areas_search(&self, search_term: &str) -> Result<AreaSearch, HttpError>;
+
+  /// Find topics created by users based on GPS coordinates (latitude and longitude).
+  fn topics_nearby(&self, lat: f32, long: f32) -> Result<TopicsNearby, HttpError>;
+
+  /// Check allowance allocated for token
+  /// `NOTE`: This call doesn't count towards your quota.
+  fn check_allowance(&self) -> Result<AllowanceCheck, HttpError>;
+}
+
+impl<C: EskomClient + ?Sized> EskomClient for &C {
+  fn get_load_shedding_status(&self) -> Result<EskomStatus, HttpError> {
+    (**self).get_load_shedding_status()
+  }
+
+  fn get_area_info(&self, area_id: &str) -> Result<AreaInfo, HttpError> {
+    (**self).get_area_info(area_id)
+  }
+
+  fn get_area_info_with_test(&self, area_id: &str, test: TestMode) -> Result<AreaInfo, HttpError> {
+    (**self).get_area_info_with_test(area_id, test)
+  }
+
+  fn areas_nearby(&self, lat: f32, long: f32) -> Result<AreaNearby, HttpError> {
+    (**self).areas_nearby(lat, long)
+  }
+
+  fn areas_search(&self, search_term: &str) -> Result<AreaSearch, HttpError> {
+    (**self).areas_search(search_term)
+  }
+
+  fn topics_nearby(&self, lat: f32, long: f32) -> Result<TopicsNearby, HttpError> {
+    (**self).topics_nearby(lat, long)
+  }
+
+  fn check_allowance(&self) -> Result<AllowanceCheck, HttpError> {
+    (**self).check_allowance()
+  }
+}
+
+#[cfg(any(feature = "async", doc))]
+/// The API calls available on every async client.
+/// Allows code to be generic over the http backend and swap in a fake client in tests.
+/// Requires the `async` feature to be enabled
+#[async_trait]
+pub trait EskomClientAsync {
+  /// The current and next loadshedding statuses for South Africa and (Optional) municipal overrides
+  async fn get_load_shedding_status(&self) -> Result<EskomStatus, HttpError>;
+
+  /// Obtain the `area_id` from Area Find or Area Search and use with this request. This single request has everything you need to monitor upcoming loadshedding events for the chosen suburb.
+  async fn get_area_info(&self, area_id: &str) -> Result<AreaInfo, HttpError>;
+
+  /// Same as `get_area_info` but returns synthetic events for the test mode without using the quota.
+  /// Useful for building UIs against realistic "loadshedding now" and "loadshedding soon" responses.
+  /// `NOTE` Default returns an error so clients that can't call the API (eg fakes) don't need to implement it
+  async fn get_area_info_with_test(
+    &self,
+    area_id: &str,
+    test: TestMode,
+  ) -> Result<AreaInfo, HttpError> {
+    let _ = (area_id, test);
+    Err(HttpError::UnknownError(
+      "Test mode isn't supported by this client".to_owned(),
+    ))
+  }
+
+  /// Find areas based on GPS coordinates (latitude and longitude).
+  async fn areas_nearby(&self, lat: f32, long: f32) -> Result<AreaNearby, HttpError>;
+
+  /// Search area based on text
+  async fn areas_search(&self, search_term: &str) -> Result<AreaSearch, HttpError>;
+
+  /// Find topics created by users based on GPS coordinates (latitude and longitude).
+  async fn topics_nearby(&self, lat: f32, long: f32) -> Result<TopicsNearby, HttpError>;
+
+  /// Check allowance allocated for token
+  /// `NOTE`: This call doesn't count towards your quota.
+  async fn check_allowance(&self) -> Result<AllowanceCheck, HttpError>;
+}
+
+#[cfg(any(feature = "async", doc))]
+#[async_trait]
+impl<C: EskomClientAsync + Sync + ?Sized> EskomClientAsync for &C {
+  async fn get_load_shedding_status(&self) -> Result<EskomStatus, HttpError> {
+    (**self).get_load_shedding_status().await
+  }
+
+  async fn get_area_info(&self, area_id: &str) -> Result<AreaInfo, HttpError> {
+    (**self).get_area_info(area_id).await
+  }
+
+  async fn get_area_info_with_test(
+    &self,
+    area_id: &str,
+    test: TestMode,
+  ) -> Result<AreaInfo, HttpError> {
+    (**self).get_area_info_with_test(area_id, test).await
+  }
+
+  async fn areas_nearby(&self, lat: f32, long: f32) -> Result<AreaNearby, HttpError> {
+    (**self).areas_nearby(lat, long).await
+  }
+
+  async fn areas_search(&self, search_term: &str) -> Result<AreaSearch, HttpError> {
+    (**self).areas_search(search_term).await
+  }
+
+  async fn topics_nearby(&self, lat: f32, long: f32) -> Result<TopicsNearby, HttpError> {
+    (**self).topics_nearby(lat, long).await
+  }
+
+  async fn check_allowance(&self) -> Result<AllowanceCheck, HttpError> {
+    (**self).check_allowance().await
   }
 }
@@ -0,0 +1,143 @@
+//! Pluggable http transports.
+//!
+//! A transport sends an [`http::Request`] and returns the raw [`http::Response`].
+//! Building the URL, adding the token and mapping the response to the output or an error is done
+//! by [`Endpoint::query`](crate::Endpoint::query) so any http library can be used by implementing [`Transport`]
+//! (or [`AsyncTransport`]).
+//!
+//! Implementations are available for `ureq::Agent`, `reqwest::blocking::Client` and `reqwest::Client`
+//! when their features are enabled.
+//! ```rust,no_run
+//! use bytes::Bytes;
+//! use eskom_se_push_api::{
+//!   errors::HttpError, status::EskomStatusUrl, transport::Transport, Endpoint,
+//! };
+//!
+//! struct MyClient;
+//!
+//! impl Transport for MyClient {
+//!   fn send(&self, request: http::Request<Bytes>) -> Result<http::Response<Bytes>, HttpError> {
+//!     // Send the request using your http library of choice
+//!     unimplemented!()
+//!   }
+//! }
+//!
+//! let status = EskomStatusUrl::default().query(&MyClient, "YOUR-TOKEN");
+//! ```
+
+#[cfg(any(feature = "async", doc))]
+use async_trait::async_trait;
+use bytes::Bytes;
+use http::{HeaderMap, StatusCode};
+use serde::de::DeserializeOwned;
+
+use crate::errors::{APIError, ApiErrorBody, ErrorContext, HttpError};
+
+/// A blocking http transport
+pub trait Transport {
+  /// Sends the request and returns the response.
+  /// `Note`: Responses with an error status should be returned as an `Ok` response,
+  /// only failures to get a response (eg timeouts or no connection) should be returned as an error.
+  fn send(&self, request: http::Request<Bytes>) -> Result<http::Response<Bytes>, HttpError>;
+}
+
+impl<T: Transport + ?Sized> Transport for &T {
+  fn send(&self, request: http::Request<Bytes>) -> Result<http::Response<Bytes>, HttpError> {
+    (**self).send(request)
+  }
+}
+
+#[cfg(any(feature = "async", doc))]
+/// An async http transport
+/// Requires the `async` feature to be enabled
+#[async_trait]
+pub trait AsyncTransport {
+  /// Sends the request and returns the response.
+  /// `Note`: Responses with an error status should be returned as an `Ok` response,
+  /// only failures to get a response (eg timeouts or no connection) should be returned as an error.
+  async fn send(&self, request: http::Request<Bytes>) -> Result<http::Response<Bytes>, HttpError>;
+}
+
+#[cfg(any(feature = "async", doc))]
+#[async_trait]
+impl<T: AsyncTransport + Sync + ?Sized> AsyncTransport for &T {
+  async fn send(&self, request: http::Request<Bytes>) -> Result<http::Response<Bytes>, HttpError> {
+    (**self).send(request).await
+  }
+}
+
+/// A transport that adds headers (eg the `User-Agent`) to the requests of the inner transport.
+/// Headers that are already set on the request aren't replaced
+pub struct DefaultHeaders<'a, T> {
+  transport: T,
+  headers: &'a HeaderMap,
+}
+
+impl<'a, T> DefaultHeaders<'a, T> {
+  pub fn new(transport: T, headers: &'a HeaderMap) -> Self {
+    DefaultHeaders { transport, headers }
+  }
+
+  fn add_headers(&self, mut request: http::Request<Bytes>) -> http::Request<Bytes> {
+    for name in self.headers.keys() {
+      if !request.headers().contains_key(name) {
+        for value in self.headers.get_all(name) {
+          request.headers_mut().append(name, value.clone());
+        }
+      }
+    }
+    request
+  }
+}
+
+impl<T: Transport> Transport for DefaultHeaders<'_, T> {
+  fn send(&self, request: http::Request<Bytes>) -> Result<http::Response<Bytes>, HttpError> {
+    self.transport.send(self.add_headers(request))
+  }
+}
+
+#[cfg(any(feature = "async", doc))]
+#[async_trait]
+impl<T: AsyncTransport + Sync> AsyncTransport for DefaultHeaders<'_, T> {
+  async fn send(&self, request: http::Request<Bytes>) -> Result<http::Response<Bytes>, HttpError> {
+    self.transport.send(self.add_headers(request)).await
+  }
+}
+
+/// Maps the raw response to the given structure or relevant error
+pub fn handle_response<T: DeserializeOwned>(
+  response: http::Response<Bytes>,
+) -> Result<T, HttpError> {
+  handle_response_with_context(response, ErrorContext::default())
+}
+
+/// Maps the raw response to the given structure or relevant error.
+/// The `context` (eg the URL of the request) is added to the error along with the status and body of the response
+pub fn handle_response_with_context<T: DeserializeOwned>(
+  response: http::Response<Bytes>,
+  context: ErrorContext,
+) -> Result<T, HttpError> {
+  let status = response.status();
+  if status.is_success() {
+    return serde_json::from_slice(response.body()).map_err(|e| HttpError::InvalidResponse {
+      context: context.with_response(&response),
+      source: Box::new(e),
+    });
+  }
+  let context = context.with_response(&response);
+  let body = || ApiErrorBody::parse(response.body());
+  let error = match status {
+    StatusCode::BAD_REQUEST => APIError::BadRequest(body()),
+    StatusCode::FORBIDDEN => APIError::Forbidden(body()),
+    StatusCode::NOT_FOUND => APIError::NotFound(body()),
+    StatusCode::TOO_MANY_REQUESTS => APIError::TooManyRequests(body()),
+    s if s.is_server_error() => APIError::ServerError(context.body.clone().unwrap_or_default()),
+    _ => return Err(HttpError::UnexpectedResponse { context }),
+  };
+  Err(HttpError::APIError { error, context })
+}
+
+/// The context of the request for errors
+pub(crate) fn request_context(request: &http::Request<Bytes>) -> ErrorContext {
+  ErrorContext::for_url(&request.uri().to_string())
+}
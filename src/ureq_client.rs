@@ -3,108 +3,262 @@
 //! # Optional
 //! Requires the `ureq` feature to be enabled
 
+use std::{io::Read, sync::Arc};
+
+use bytes::Bytes;
+use http::{HeaderMap, StatusCode};
 use serde::de::DeserializeOwned;
 
 use crate::{
-  allowance::{AllowanceCheck, AllowanceCheckURL},
-  area_info::{AreaInfo, AreaInfoURLBuilder},
+  allowance::{AllowanceCheck, AllowanceCheckURLBuilder},
+  area_info::{AreaInfo, AreaInfoURLBuilder, TestMode},
   area_nearby::{AreaNearby, AreasNearbyURLBuilder},
   area_search::{AreaSearch, AreaSearchURLBuilder},
-  errors::{APIError, HttpError},
-  get_token_from_env,
-  status::{EskomStatus, EskomStatusUrl},
+  cache::{Cache, ResponseCache},
+  client_builder::ClientBuilder,
+  constants::BASE_URL,
+  errors::{BoxError, ConfigError, ErrorContext, HttpError},
+  get_base_url_from_env,
+  retry::{Retry, RetryPolicy},
+  status::{EskomStatus, EskomStatusUrlBuilder},
   topics_nearby::{TopicsNearby, TopicsNearbyUrlBuilder},
-  Endpoint,
+  transport::{handle_response_with_context, DefaultHeaders, Transport},
+  try_get_token_from_env, Endpoint, EskomClient,
 };
 
 pub struct UreqClient {
+  agent: ureq::Agent,
   token: String,
+  base_url: String,
+  retry_policy: RetryPolicy,
+  cache: Option<Arc<dyn ResponseCache>>,
+  headers: HeaderMap,
 }
 
 impl UreqClient {
   /// Create new client using the `ureq` Http client
   /// `token` is the Eskom API token
   pub fn new(token: String) -> Self {
-    UreqClient { token }
+    UreqClient::from_parts(ureq::agent(), token, HeaderMap::new())
+  }
+
+  fn from_parts(agent: ureq::Agent, token: String, headers: HeaderMap) -> Self {
+    UreqClient {
+      agent,
+      token,
+      base_url: BASE_URL.to_owned(),
+      retry_policy: RetryPolicy::never(),
+      cache: None,
+      headers,
+    }
+  }
+
+  /// Create new client like [`UreqClient::new`] but returns an error instead of panicking
+  /// if the token contains characters that aren't allowed in a header
+  pub fn try_new(token: String) -> Result<Self, ConfigError> {
+    ClientBuilder::new(token).wrap_ureq(ureq::agent())
   }
 
   /// Creates new instance of Eskom API using token as a env variable.
   /// Uses the [dotenv](https://crates.io/crates/dotenv) crate so it will load .env files if available.
+  /// The base URL is also read from the `ESKOMSEPUSH_BASE_URL` env variable if it is set.
   /// `Note`: The default variable name is `ESKOMSEPUSH_API_KEY` if var_name is set to `None`.
-  /// `Note`: It will panic the env variable doesn't exist.
+  /// `Note`: It will panic the env variable doesn't exist. Use [`UreqClient::try_from_env`] to handle the error instead.
   pub fn new_with_env(var_name: Option<&str>) -> Self {
-    match get_token_from_env(var_name) {
-      Ok(val) => UreqClient { token: val },
+    match UreqClient::try_from_env(var_name) {
+      Ok(client) => client,
       Err(e) => panic!("Error: {}", e),
     }
   }
 
+  /// Creates new instance of Eskom API using token as a env variable like [`UreqClient::new_with_env`]
+  /// but returns an error if the variable doesn't exist or the client can't be created
+  pub fn try_from_env(var_name: Option<&str>) -> Result<Self, ConfigError> {
+    let token = try_get_token_from_env(var_name)?;
+    Ok(UreqClient::try_new(token)?.with_base_url(get_base_url_from_env(None)))
+  }
+
+  /// Overrides the base URL of the API eg a staging proxy, caching gateway or a local mock server.
+  /// Defaults to [`BASE_URL`](crate::constants::BASE_URL)
+  pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+    self.base_url = base_url.into();
+    self
+  }
+
+  /// Retries transient failures using the policy. By default requests aren't retried
+  pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+    self.retry_policy = retry_policy;
+    self
+  }
+
+  /// Serves responses from the cache while they are fresh instead of using the quota.
+  /// The cache can be shared between clients
+  pub fn with_cache(mut self, cache: Arc<dyn ResponseCache>) -> Self {
+    self.cache = Some(cache);
+    self
+  }
+
+  /// The transport used to make the API calls
+  fn transport(&self) -> Cache<'_, Retry<'_, DefaultHeaders<'_, &ureq::Agent>>> {
+    Cache::new(
+      Retry::new(
+        DefaultHeaders::new(&self.agent, &self.headers),
+        &self.retry_policy,
+      ),
+      self.cache.as_deref(),
+    )
+  }
+
   /// The current and next loadshedding statuses for South Africa and (Optional) municipal overrides
   /// `eskom` is the National status
   /// Other keys in the `status` refer to different municipalities and potential overrides from the National status; most typically present is the key for `capetown`
   pub fn get_load_shedding_status(&self) -> Result<EskomStatus, HttpError> {
-    let c = EskomStatusUrl::default();
-    c.ureq(&self.token)
+    let c = EskomStatusUrlBuilder::default()
+      .base_url(self.base_url.as_str())
+      .build()
+      .map_err(|e| HttpError::UnknownError(e.to_string()))?;
+    c.query(&self.transport(), &self.token)
   }
 
   /// Obtain the `area_id` from Area Find or Area Search and use with this request. This single request has everything you need to monitor upcoming loadshedding events for the chosen suburb.
   pub fn get_area_info(&self, area_id: &str) -> Result<AreaInfo, HttpError> {
-    let t = AreaInfoURLBuilder::default()
-      .area_id(area_id.to_owned())
-      .build()
-      .map_err(|_| HttpError::AreaIdNotSet)?;
-    t.ureq(&self.token)
+    self.area_info(area_id, None)
+  }
+
+  /// Gets the info of the area, with synthetic events if the test mode is set
+  fn area_info(&self, area_id: &str, test: Option<TestMode>) -> Result<AreaInfo, HttpError> {
+    let mut builder = AreaInfoURLBuilder::default();
+    builder
+      .base_url(self.base_url.as_str())
+      .area_id(area_id.to_owned());
+    if let Some(test) = test {
+      builder.test(test);
+    }
+    let t = builder.build().map_err(|_| HttpError::AreaIdNotSet)?;
+    t.query(&self.transport(), &self.token)
   }
 
   /// Find areas based on GPS coordinates (latitude and longitude).
   /// The first area returned is typically the best choice for the coordinates - as it's closest to the GPS coordinates provided. However it could be that you are in the second or third area.
   pub fn areas_nearby(&self, lat: f32, long: f32) -> Result<AreaNearby, HttpError> {
     let t = AreasNearbyURLBuilder::default()
+      .base_url(self.base_url.as_str())
       .latitude(lat)
       .longitude(long)
       .build()
       .map_err(|_| HttpError::LongitudeOrLatitudeNotSet {
-        longitude: lat,
-        latitude: long,
+        longitude: long,
+        latitude: lat,
       })?;
-    t.ureq(&self.token)
+    t.query(&self.transport(), &self.token)
   }
 
   /// Search area based on text
   pub fn areas_search(&self, search_term: &str) -> Result<AreaSearch, HttpError> {
     let t = AreaSearchURLBuilder::default()
+      .base_url(self.base_url.as_str())
       .search_term(search_term)
       .build()
       .map_err(|_| HttpError::SearchTextNotSet)?;
-    t.ureq(&self.token)
+    t.query(&self.transport(), &self.token)
   }
 
   /// Find topics created by users based on GPS coordinates (latitude and longitude). Can use this to detect if there is a potential outage/problem nearby
   pub fn topics_nearby(&self, lat: f32, long: f32) -> Result<TopicsNearby, HttpError> {
     let t = TopicsNearbyUrlBuilder::default()
+      .base_url(self.base_url.as_str())
       .latitude(lat)
       .longitude(long)
       .build()
       .map_err(|_| HttpError::LongitudeOrLatitudeNotSet {
-        longitude: lat,
-        latitude: long,
+        longitude: long,
+        latitude: lat,
       })?;
-    t.ureq(&self.token)
+    t.query(&self.transport(), &self.token)
   }
 
   /// Check allowance allocated for token
   /// `NOTE`: This call doesn't count towards your quota.
   pub fn check_allowance(&self) -> Result<AllowanceCheck, HttpError> {
-    let t = AllowanceCheckURL::default();
-    t.ureq(&self.token)
+    let t = AllowanceCheckURLBuilder::default()
+      .base_url(self.base_url.as_str())
+      .build()
+      .map_err(|e| HttpError::UnknownError(e.to_string()))?;
+    t.query(&self.transport(), &self.token)
+  }
+}
+
+impl ClientBuilder {
+  /// Builds a client using the `ureq` Http client
+  pub fn build_ureq(self) -> Result<UreqClient, ConfigError> {
+    let mut agent = ureq::AgentBuilder::new();
+    if let Some(timeout) = self.connect_timeout {
+      agent = agent.timeout_connect(timeout);
+    }
+    if let Some(timeout) = self.read_timeout {
+      agent = agent.timeout_read(timeout);
+    }
+    if let Some(proxy) = &self.proxy {
+      let proxy = ureq::Proxy::new(proxy).map_err(|e| ConfigError::ClientBuild(Box::new(e)))?;
+      agent = agent.proxy(proxy);
+    }
+    let headers = self.default_headers()?;
+    Ok(UreqClient::from_parts(agent.build(), self.token, headers))
+  }
+
+  /// Builds a client that uses an existing `ureq` agent.
+  /// `Note`: The timeouts and proxy need to be set on the agent, an error is returned if they are set on the builder
+  pub fn wrap_ureq(self, agent: ureq::Agent) -> Result<UreqClient, ConfigError> {
+    self.check_wrappable()?;
+    let headers = self.default_headers()?;
+    Ok(UreqClient::from_parts(agent, self.token, headers))
+  }
+}
+
+impl EskomClient for UreqClient {
+  fn get_load_shedding_status(&self) -> Result<EskomStatus, HttpError> {
+    UreqClient::get_load_shedding_status(self)
+  }
+
+  fn get_area_info(&self, area_id: &str) -> Result<AreaInfo, HttpError> {
+    UreqClient::get_area_info(self, area_id)
+  }
+
+  fn get_area_info_with_test(&self, area_id: &str, test: TestMode) -> Result<AreaInfo, HttpError> {
+    self.area_info(area_id, Some(test))
+  }
+
+  fn areas_nearby(&self, lat: f32, long: f32) -> Result<AreaNearby, HttpError> {
+    UreqClient::areas_nearby(self, lat, long)
+  }
+
+  fn areas_search(&self, search_term: &str) -> Result<AreaSearch, HttpError> {
+    UreqClient::areas_search(self, search_term)
+  }
+
+  fn topics_nearby(&self, lat: f32, long: f32) -> Result<TopicsNearby, HttpError> {
+    UreqClient::topics_nearby(self, lat, long)
+  }
+
+  fn check_allowance(&self) -> Result<AllowanceCheck, HttpError> {
+    UreqClient::check_allowance(self)
   }
 }
 
 /// A response handler for `ureq` to map the response to the given structure or relevant error
-/// ```rust
-/// let statusUrl = EskomStatusUrlBuilder::default().build().unwrap();
+/// ```rust,no_run
+/// use eskom_se_push_api::{
+///   constants::TOKEN_KEY,
+///   status::{EskomStatus, EskomStatusUrlBuilder},
+///   ureq_client::handle_ureq_response,
+///   Endpoint,
+/// };
 ///
-/// let api_response  = ureq::get(statusUrl.url().unwrap().as_str()).set(TOKEN_KEY, "YOUR-TOKEN").call();
+/// let status_url = EskomStatusUrlBuilder::default().build().unwrap();
+///
+/// let api_response = ureq::get(status_url.url().unwrap().as_str())
+///   .set(TOKEN_KEY, "YOUR-TOKEN")
+///   .call();
 /// let response = handle_ureq_response::<EskomStatus>(api_response);
 /// ```
 /// `response` is the ureq API response
@@ -114,19 +268,79 @@ pub fn handle_ureq_response<T: DeserializeOwned>(
   response: Result<ureq::Response, ureq::Error>,
 ) -> Result<T, HttpError> {
   match response {
-    Ok(resp) => resp
-      .into_json::<T>()
-      .map_err(|e| HttpError::UnknownError(e.to_string())),
-    Err(ureq::Error::Status(code, response)) => match code {
-      400 => Err(HttpError::APIError(APIError::BadRequest)),
-      403 => Err(HttpError::APIError(APIError::Forbidden)),
-      404 => Err(HttpError::APIError(APIError::NotFound)),
-      429 => Err(HttpError::APIError(APIError::TooManyRequests)),
-      500..=509 => Err(HttpError::APIError(APIError::ServerError(
-        response.into_string().unwrap(),
-      ))),
-      _a => Err(HttpError::Unknown),
-    },
-    Err(_) => Err(HttpError::NoInternet),
+    Ok(resp) | Err(ureq::Error::Status(_, resp)) => {
+      let context = ErrorContext::for_url(resp.get_url());
+      handle_response_with_context(into_http_response(resp)?, context)
+    }
+    Err(ureq::Error::Transport(err)) => Err(transport_error(err)),
+  }
+}
+
+impl Transport for ureq::Agent {
+  fn send(&self, request: http::Request<Bytes>) -> Result<http::Response<Bytes>, HttpError> {
+    let (parts, body) = request.into_parts();
+    let mut req = self.request(parts.method.as_str(), &parts.uri.to_string());
+    // `ureq` replaces a header when it is set again so repeated values are joined into one
+    for name in parts.headers.keys() {
+      let values = parts
+        .headers
+        .get_all(name)
+        .iter()
+        .map(|value| value.to_str())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| HttpError::UnknownError(e.to_string()))?;
+      req = req.set(name.as_str(), &values.join(", "));
+    }
+    let response = if body.is_empty() {
+      req.call()
+    } else {
+      req.send_bytes(&body)
+    };
+    match response {
+      Ok(resp) | Err(ureq::Error::Status(_, resp)) => into_http_response(resp),
+      Err(ureq::Error::Transport(err)) => Err(transport_error(err)),
+    }
+  }
+}
+
+/// Converts the `ureq` response into a [`http::Response`] by reading the whole body
+fn into_http_response(resp: ureq::Response) -> Result<http::Response<Bytes>, HttpError> {
+  let mut builder = http::Response::builder().status(resp.status());
+  for name in resp.headers_names() {
+    for value in resp.all(&name) {
+      builder = builder.header(name.as_str(), value);
+    }
+  }
+  let mut context = ErrorContext::for_url(resp.get_url());
+  context.status = StatusCode::from_u16(resp.status()).ok();
+  let mut body = Vec::new();
+  resp
+    .into_reader()
+    .read_to_end(&mut body)
+    .map_err(|e| HttpError::InvalidResponse {
+      context,
+      source: Box::new(e),
+    })?;
+  Ok(builder.body(Bytes::from(body))?)
+}
+
+fn transport_error(err: ureq::Transport) -> HttpError {
+  let timed_out = std::error::Error::source(&err)
+    .and_then(|e| e.downcast_ref::<std::io::Error>())
+    .is_some_and(|e| {
+      matches!(
+        e.kind(),
+        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+      )
+    });
+  let context = err
+    .url()
+    .map(|url| ErrorContext::for_url(url.as_str()))
+    .unwrap_or_default();
+  let source = Some(Box::new(err) as BoxError);
+  if timed_out {
+    HttpError::Timeout { context, source }
+  } else {
+    HttpError::NoInternet { context, source }
   }
 }
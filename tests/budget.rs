@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use eskom_se_push_api::{
+  area_info::TestMode,
+  budget::{BudgetConfigBuilder, BudgetMode, Budgeted},
+  errors::{APIError, HttpError},
+  mock_server::{MockResponse, MockServer},
+  ureq_client::UreqClient,
+  EskomClient,
+};
+
+const TOKEN: &str = "test-token";
+
+fn client(server: &MockServer) -> UreqClient {
+  UreqClient::new(TOKEN.to_owned()).with_base_url(server.base_url())
+}
+
+#[test]
+fn refuses_calls_that_dip_into_the_reserve() {
+  let server = MockServer::start(TOKEN);
+  // The mock allowance has 38 calls remaining
+  let config = BudgetConfigBuilder::default().reserve(37).build().unwrap();
+  let api = Budgeted::new(client(&server), config);
+  assert!(api.get_load_shedding_status().is_ok());
+  assert!(matches!(
+    api.get_area_info("tshwane-6-brooklyn"),
+    Err(HttpError::BudgetExceeded {
+      remaining: 37,
+      reserve: 37
+    })
+  ));
+  assert_eq!(api.remaining(), Some(37));
+  assert_eq!(api.spend().get("status"), Some(&1));
+  assert_eq!(server.hits("api_allowance"), 1);
+  assert_eq!(server.hits("area"), 0);
+  // Test mode doesn't use the quota so it is still allowed
+  assert!(api
+    .get_area_info_with_test("tshwane-6-brooklyn", TestMode::Future)
+    .is_ok());
+  assert_eq!(api.spend().get("area"), None);
+}
+
+#[test]
+fn defers_until_the_allowance_frees_up() {
+  let server = MockServer::start(TOKEN);
+  server.respond_once(
+    "api_allowance",
+    MockResponse::new(
+      200,
+      r#"{"allowance": {"count": 50, "limit": 50, "type": "daily"}}"#,
+    ),
+  );
+  let config = BudgetConfigBuilder::default()
+    .refresh_interval(Duration::from_millis(10))
+    .mode(BudgetMode::Defer {
+      max_wait: Duration::from_secs(1),
+    })
+    .build()
+    .unwrap();
+  let api = Budgeted::new(client(&server), config);
+  assert!(api.get_load_shedding_status().is_ok());
+  assert_eq!(server.hits("api_allowance"), 2);
+}
+
+#[test]
+fn defers_without_a_busy_loop() {
+  let server = MockServer::start(TOKEN);
+  server.respond_with(
+    "api_allowance",
+    MockResponse::new(
+      200,
+      r#"{"allowance": {"count": 50, "limit": 50, "type": "daily"}}"#,
+    ),
+  );
+  // Checks the allowance before every call
+  let config = BudgetConfigBuilder::default()
+    .refresh_interval(Duration::ZERO)
+    .mode(BudgetMode::Defer {
+      max_wait: Duration::from_millis(50),
+    })
+    .build()
+    .unwrap();
+  let api = Budgeted::new(client(&server), config);
+  assert!(matches!(
+    api.get_load_shedding_status(),
+    Err(HttpError::BudgetExceeded { .. })
+  ));
+  assert_eq!(server.hits("api_allowance"), 2);
+  assert_eq!(server.hits("status"), 0);
+}
+
+#[test]
+fn too_many_requests_exhausts_the_budget() {
+  let server = MockServer::start(TOKEN);
+  server.respond_once("status", MockResponse::status(429));
+  let api = Budgeted::new(client(&server), Default::default());
+  assert!(matches!(
+    api.get_load_shedding_status(),
+    Err(HttpError::APIError {
+      error: APIError::TooManyRequests(_),
+      ..
+    })
+  ));
+  assert_eq!(api.remaining(), Some(0));
+  assert!(matches!(
+    api.get_load_shedding_status(),
+    Err(HttpError::BudgetExceeded { .. })
+  ));
+  assert!(api.projected_exhaustion().is_some());
+}
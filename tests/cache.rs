@@ -0,0 +1,102 @@
+use std::{sync::Arc, time::Duration};
+
+use eskom_se_push_api::{
+  cache::{CacheTtlBuilder, MemoryCache, ResponseCache},
+  mock_server::{MockResponse, MockServer},
+  reqwest_async_client::ReqwestAsyncCLient,
+  reqwest_blocking_client::ReqwestBlockingCLient,
+  ureq_client::UreqClient,
+};
+
+const TOKEN: &str = "test-token";
+
+#[test]
+fn serves_fresh_responses_from_the_cache() {
+  let server = MockServer::start(TOKEN);
+  let cache = Arc::new(MemoryCache::default());
+  let api = UreqClient::new(TOKEN.to_owned())
+    .with_base_url(server.base_url())
+    .with_cache(cache.clone());
+  let first = api.get_load_shedding_status().unwrap();
+  let second = api.get_load_shedding_status().unwrap();
+  assert_eq!(first, second);
+  assert_eq!(server.hits("status"), 1);
+
+  // Different queries are cached separately
+  api.get_area_info("tshwane-6-brooklyn").unwrap();
+  api.get_area_info("capetown-5-brooklyn").unwrap();
+  api.get_area_info("tshwane-6-brooklyn").unwrap();
+  assert_eq!(server.hits("area"), 2);
+
+  cache.invalidate_endpoint("status");
+  api.get_load_shedding_status().unwrap();
+  assert_eq!(server.hits("status"), 2);
+  assert_eq!(server.hits("area"), 2);
+}
+
+#[test]
+fn cache_is_shared_between_clients() {
+  let server = MockServer::start(TOKEN);
+  let cache = Arc::new(MemoryCache::default());
+  let ureq = UreqClient::new(TOKEN.to_owned())
+    .with_base_url(server.base_url())
+    .with_cache(cache.clone());
+  let reqwest = ReqwestBlockingCLient::new(TOKEN.to_owned())
+    .with_base_url(server.base_url())
+    .with_cache(cache.clone());
+  ureq.areas_search("brooklyn").unwrap();
+  reqwest.areas_search("brooklyn").unwrap();
+  assert_eq!(server.hits("areas_search"), 1);
+
+  cache.clear();
+  reqwest.areas_search("brooklyn").unwrap();
+  assert_eq!(server.hits("areas_search"), 2);
+}
+
+#[test]
+fn errors_and_allowance_are_not_cached() {
+  let server = MockServer::start(TOKEN);
+  let api = UreqClient::new(TOKEN.to_owned())
+    .with_base_url(server.base_url())
+    .with_cache(Arc::new(MemoryCache::default()));
+  server.respond_once("status", MockResponse::status(503));
+  assert!(api.get_load_shedding_status().is_err());
+  assert!(api.get_load_shedding_status().is_ok());
+  assert_eq!(server.hits("status"), 2);
+
+  api.check_allowance().unwrap();
+  api.check_allowance().unwrap();
+  assert_eq!(server.hits("api_allowance"), 2);
+}
+
+#[test]
+fn expired_responses_are_refetched() {
+  let server = MockServer::start(TOKEN);
+  let ttl = CacheTtlBuilder::default()
+    .status(Duration::from_millis(50))
+    .build()
+    .unwrap();
+  let cache = Arc::new(MemoryCache::new(ttl));
+  let api = UreqClient::new(TOKEN.to_owned())
+    .with_base_url(server.base_url())
+    .with_cache(cache.clone());
+  api.get_load_shedding_status().unwrap();
+  std::thread::sleep(Duration::from_millis(100));
+  cache.evict_expired();
+  assert!(cache
+    .get(&format!("{}/status", server.base_url()))
+    .is_none());
+  api.get_load_shedding_status().unwrap();
+  assert_eq!(server.hits("status"), 2);
+}
+
+#[tokio::test]
+async fn async_client_uses_the_cache() {
+  let server = MockServer::start(TOKEN);
+  let api = ReqwestAsyncCLient::new(TOKEN.to_owned())
+    .with_base_url(server.base_url())
+    .with_cache(Arc::new(MemoryCache::default()));
+  api.get_area_info("tshwane-6-brooklyn").await.unwrap();
+  api.get_area_info("tshwane-6-brooklyn").await.unwrap();
+  assert_eq!(server.hits("area"), 1);
+}
@@ -0,0 +1,176 @@
+#![cfg(feature = "cli")]
+
+use std::process::{Command, Output};
+
+use eskom_se_push_api::mock_server::{MockResponse, MockServer};
+
+const TOKEN: &str = "test-token";
+
+fn esp(server: &MockServer, args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_esp"))
+    .args(args)
+    .env("ESKOMSEPUSH_API_KEY", TOKEN)
+    .env("ESKOMSEPUSH_BASE_URL", server.base_url())
+    .env("ESKOMSEPUSH_CONFIG", "does-not-exist.toml")
+    .output()
+    .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+  assert!(output.status.success(), "{:?}", output);
+  String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn prints_tables() {
+  let server = MockServer::start(TOKEN);
+  let status = stdout(&esp(&server, &["status"]));
+  assert!(status.starts_with("Area"));
+  assert!(status.lines().nth(2).unwrap().contains("Stage 3"));
+
+  let area = stdout(&esp(&server, &["area", "tshwane-6-brooklyn"]));
+  assert!(area.contains("City of Tshwane"));
+  assert!(area.contains("Stage 2"));
+
+  let search = stdout(&esp(&server, &["search", "brooklyn"]));
+  assert!(search.contains("tshwane-6-brooklyn"));
+
+  let allowance = stdout(&esp(&server, &["allowance"]));
+  assert!(allowance.lines().nth(2).unwrap().starts_with("12"));
+}
+
+#[test]
+fn prints_json() {
+  let server = MockServer::start(TOKEN);
+  let nearby = stdout(&esp(&server, &["nearby", "-25.7479", "28.2293", "--json"]));
+  let nearby: eskom_se_push_api::area_nearby::AreaNearby = serde_json::from_str(&nearby).unwrap();
+  assert!(!nearby.areas.is_empty());
+  assert_eq!(
+    server.requests()[0].url,
+    "/areas_nearby?lat=-25.7479&long=28.2293"
+  );
+
+  let topics = stdout(&esp(&server, &["--json", "topics", "-25.7479", "28.2293"]));
+  assert!(serde_json::from_str::<eskom_se_push_api::topics_nearby::TopicsNearby>(&topics).is_ok());
+
+  // The note is printed as the API sent it
+  server.respond_once(
+    "area",
+    MockResponse::new(
+      200,
+      r#"{"events": [{"end": "2022-08-08T22:30:00+02:00", "note": "Stage 2 (TESTING: future)", "start": "2022-08-08T20:00:00+02:00"}], "info": {"name": "Brooklyn (6)", "region": "City of Tshwane"}, "schedule": {"days": [], "source": ""}}"#,
+    ),
+  );
+  let area = stdout(&esp(
+    &server,
+    &["area", "tshwane-6-brooklyn", "--test", "future", "--json"],
+  ));
+  assert!(area.contains("\"note\": \"Stage 2 (TESTING: future)\""));
+}
+
+#[test]
+fn reports_errors() {
+  let server = MockServer::start(TOKEN);
+  server.respond_with("status", MockResponse::status(403));
+  let output = esp(&server, &["status"]);
+  assert!(!output.status.success());
+  assert!(
+    String::from_utf8_lossy(&output.stderr).starts_with("Error: API Error: Not Authenticated")
+  );
+
+  let output = esp(
+    &server,
+    &["--token-env", "ESP_TEST_MISSING_TOKEN", "status"],
+  );
+  assert!(!output.status.success());
+  assert!(String::from_utf8_lossy(&output.stderr).contains("ESP_TEST_MISSING_TOKEN"));
+}
+
+#[test]
+fn uses_the_areas_in_the_config() {
+  let server = MockServer::start(TOKEN);
+  let path = std::env::temp_dir().join(format!("esp-cli-{}.toml", std::process::id()));
+  std::fs::write(
+    &path,
+    format!(
+      r#"
+token_env = "ESP_TEST_TOKEN"
+base_url = "{}"
+
+[areas.home]
+id = "tshwane-6-brooklyn"
+name = "Brooklyn (6)"
+region = "City of Tshwane"
+"#,
+      server.base_url()
+    ),
+  )
+  .unwrap();
+  let output = Command::new(env!("CARGO_BIN_EXE_esp"))
+    .args(["area", "home", "--config"])
+    .arg(&path)
+    .env("ESP_TEST_TOKEN", TOKEN)
+    .output()
+    .unwrap();
+  assert!(stdout(&output).contains("City of Tshwane"));
+  assert_eq!(server.requests()[0].url, "/area?id=tshwane-6-brooklyn");
+
+  let output = Command::new(env!("CARGO_BIN_EXE_esp"))
+    .args(["areas", "--config"])
+    .arg(&path)
+    .output()
+    .unwrap();
+  assert!(stdout(&output)
+    .lines()
+    .nth(2)
+    .unwrap()
+    .starts_with("home  tshwane-6-brooklyn"));
+  std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn serves_the_feeds() {
+  use std::io::{BufRead, BufReader};
+
+  let server = MockServer::start(TOKEN);
+  let mut child = Command::new(env!("CARGO_BIN_EXE_esp"))
+    .args([
+      "serve",
+      "--addr",
+      "127.0.0.1:0",
+      "--area",
+      "tshwane-6-brooklyn",
+    ])
+    .env("ESKOMSEPUSH_API_KEY", TOKEN)
+    .env("ESKOMSEPUSH_BASE_URL", server.base_url())
+    .env("ESKOMSEPUSH_CONFIG", "does-not-exist.toml")
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .unwrap();
+  // The reader is kept open until the server is killed so printing doesn't fail
+  let mut stderr = BufReader::new(child.stderr.take().unwrap());
+  let mut line = String::new();
+  stderr.read_line(&mut line).unwrap();
+  let base_url = line.trim().strip_prefix("Serving feeds on ").unwrap();
+  let mut url = String::new();
+  stderr.read_line(&mut url).unwrap();
+  assert_eq!(
+    url.trim(),
+    format!("{}/calendar/tshwane-6-brooklyn.ics", base_url)
+  );
+  let calendar = ureq::get(&format!("{}/calendar/tshwane-6-brooklyn.ics", base_url))
+    .call()
+    .unwrap()
+    .into_string();
+  // Only the areas passed are served
+  let other = ureq::get(&format!("{}/calendar/eskde-10-fourways.ics", base_url)).call();
+  child.kill().unwrap();
+  child.wait().unwrap();
+  assert!(calendar.unwrap().starts_with("BEGIN:VCALENDAR"));
+  assert!(matches!(other, Err(ureq::Error::Status(404, _))));
+
+  // There is nothing to serve without saved areas or --area
+  let output = esp(&server, &["serve", "--addr", "127.0.0.1:0"]);
+  assert!(!output.status.success());
+  assert!(String::from_utf8_lossy(&output.stderr).contains("--area"));
+}
@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use eskom_se_push_api::{
+  client_builder::ClientBuilder, errors::ConfigError, mock_server::MockServer,
+};
+
+const TOKEN: &str = "test-token";
+
+fn builder() -> ClientBuilder {
+  ClientBuilder::new(TOKEN)
+    .user_agent("my-app/1.0")
+    .header("X-Request-Source", "tests")
+    .header("token", "not-the-token")
+}
+
+#[test]
+fn ureq_sends_the_default_headers() {
+  let server = MockServer::start(TOKEN);
+  let api = builder()
+    .connect_timeout(Duration::from_secs(5))
+    .read_timeout(Duration::from_secs(5))
+    .build_ureq()
+    .unwrap()
+    .with_base_url(server.base_url());
+  api.check_allowance().unwrap();
+  let request = &server.requests()[0];
+  assert_eq!(request.token.as_deref(), Some(TOKEN));
+  assert_eq!(request.header("user-agent"), Some("my-app/1.0"));
+  assert_eq!(request.header("x-request-source"), Some("tests"));
+}
+
+#[test]
+fn wraps_an_existing_agent() {
+  let server = MockServer::start(TOKEN);
+  let api = builder()
+    .wrap_ureq(ureq::agent())
+    .unwrap()
+    .with_base_url(server.base_url());
+  api.check_allowance().unwrap();
+  assert_eq!(
+    server.requests()[0].header("user-agent"),
+    Some("my-app/1.0")
+  );
+  assert!(matches!(
+    builder()
+      .read_timeout(Duration::from_secs(5))
+      .wrap_ureq(ureq::agent()),
+    Err(ConfigError::ClientBuild(_))
+  ));
+}
+
+#[test]
+fn reqwest_blocking_sends_the_default_headers() {
+  let server = MockServer::start(TOKEN);
+  let api = builder()
+    .read_timeout(Duration::from_secs(5))
+    .build_reqwest_blocking()
+    .unwrap()
+    .with_base_url(server.base_url());
+  api.check_allowance().unwrap();
+  let request = &server.requests()[0];
+  assert_eq!(request.token.as_deref(), Some(TOKEN));
+  assert_eq!(request.header("user-agent"), Some("my-app/1.0"));
+}
+
+#[tokio::test]
+async fn reqwest_async_sends_the_default_headers() {
+  let server = MockServer::start(TOKEN);
+  let api = builder()
+    .build_reqwest_async()
+    .unwrap()
+    .with_base_url(server.base_url());
+  api.check_allowance().await.unwrap();
+  assert_eq!(
+    server.requests()[0].header("x-request-source"),
+    Some("tests")
+  );
+}
+
+#[test]
+fn rejects_invalid_headers() {
+  assert!(matches!(
+    ClientBuilder::new(TOKEN)
+      .header("bad header", "value")
+      .build_ureq(),
+    Err(ConfigError::InvalidHeaderName { .. })
+  ));
+  assert!(matches!(
+    ClientBuilder::new(TOKEN)
+      .user_agent("bad\nagent")
+      .build_ureq(),
+    Err(ConfigError::InvalidHeader { .. })
+  ));
+  assert!(matches!(
+    ClientBuilder::new(TOKEN)
+      .proxy("not a proxy")
+      .build_reqwest_blocking(),
+    Err(ConfigError::ClientBuild(_))
+  ));
+}
@@ -0,0 +1,73 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use eskom_se_push_api::{
+  cache::{CacheTtl, CacheTtlBuilder, ResponseCache},
+  disk_cache::DiskCache,
+  mock_server::{MockResponse, MockServer},
+  ureq_client::UreqClient,
+};
+
+const TOKEN: &str = "test-token";
+
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("eskom-se-push-{}-{}", name, std::process::id()));
+  let _ = std::fs::remove_dir_all(&dir);
+  dir
+}
+
+#[test]
+fn survives_restarts() {
+  let server = MockServer::start(TOKEN);
+  let dir = temp_dir("restart");
+  for _ in 0..2 {
+    let cache = DiskCache::new(&dir, CacheTtl::default()).unwrap();
+    let api = UreqClient::new(TOKEN.to_owned())
+      .with_base_url(server.base_url())
+      .with_cache(Arc::new(cache));
+    let info = api.get_area_info("tshwane-6-brooklyn").unwrap();
+    assert_eq!(info.info.name, "Brooklyn (6)");
+  }
+  assert_eq!(server.hits("area"), 1);
+
+  let cache = DiskCache::new(&dir, CacheTtl::default()).unwrap();
+  let url = format!("{}/area?id=tshwane-6-brooklyn", server.base_url());
+  assert!(cache.get(&url).is_some());
+  cache.invalidate(&url);
+  assert!(cache.get(&url).is_none());
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn stale_if_error() {
+  let server = MockServer::start(TOKEN);
+  let dir = temp_dir("stale");
+  let ttl = CacheTtlBuilder::default()
+    .status(Duration::from_millis(1))
+    .build()
+    .unwrap();
+  let cache = Arc::new(DiskCache::new(&dir, ttl).unwrap().with_stale_if_error(true));
+  let api = UreqClient::new(TOKEN.to_owned())
+    .with_base_url(server.base_url())
+    .with_cache(cache.clone());
+  let fresh = api.get_load_shedding_status().unwrap();
+  std::thread::sleep(Duration::from_millis(10));
+
+  server.respond_once("status", MockResponse::status(503));
+  assert_eq!(api.get_load_shedding_status().unwrap(), fresh);
+  assert_eq!(server.hits("status"), 2);
+
+  // Client errors aren't hidden by the stale response
+  server.respond_once("status", MockResponse::status(403));
+  assert!(api.get_load_shedding_status().is_err());
+
+  let base_url = server.base_url();
+  drop(server);
+  let api = UreqClient::new(TOKEN.to_owned())
+    .with_base_url(base_url)
+    .with_cache(cache.clone());
+  assert_eq!(api.get_load_shedding_status().unwrap(), fresh);
+
+  cache.clear();
+  assert!(api.get_load_shedding_status().is_err());
+  let _ = std::fs::remove_dir_all(&dir);
+}
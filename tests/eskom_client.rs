@@ -0,0 +1,84 @@
+use eskom_se_push_api::{
+  allowance::{Allowance, AllowanceCheck},
+  area_info::{AreaInfo, TestMode},
+  area_nearby::AreaNearby,
+  area_search::AreaSearch,
+  errors::HttpError,
+  mock_server::MockServer,
+  reqwest_async_client::ReqwestAsyncCLient,
+  reqwest_blocking_client::ReqwestBlockingCLient,
+  status::EskomStatus,
+  topics_nearby::TopicsNearby,
+  ureq_client::UreqClient,
+  EskomClient, EskomClientAsync,
+};
+
+const TOKEN: &str = "test-token";
+
+fn remaining_calls(api: &impl EskomClient) -> i64 {
+  let allowance = api.check_allowance().unwrap().allowance;
+  allowance.limit - allowance.count
+}
+
+async fn remaining_calls_async(api: &impl EskomClientAsync) -> i64 {
+  let allowance = api.check_allowance().await.unwrap().allowance;
+  allowance.limit - allowance.count
+}
+
+struct FakeClient;
+
+impl EskomClient for FakeClient {
+  fn get_load_shedding_status(&self) -> Result<EskomStatus, HttpError> {
+    Ok(EskomStatus::default())
+  }
+
+  fn get_area_info(&self, _area_id: &str) -> Result<AreaInfo, HttpError> {
+    Ok(AreaInfo::default())
+  }
+
+  fn areas_nearby(&self, _lat: f32, _long: f32) -> Result<AreaNearby, HttpError> {
+    Ok(AreaNearby::default())
+  }
+
+  fn areas_search(&self, _search_term: &str) -> Result<AreaSearch, HttpError> {
+    Ok(AreaSearch::default())
+  }
+
+  fn topics_nearby(&self, _lat: f32, _long: f32) -> Result<TopicsNearby, HttpError> {
+    Ok(TopicsNearby::default())
+  }
+
+  fn check_allowance(&self) -> Result<AllowanceCheck, HttpError> {
+    Ok(AllowanceCheck {
+      allowance: Allowance {
+        count: 1,
+        limit: 5,
+        type_field: "daily".to_owned(),
+      },
+    })
+  }
+}
+
+#[test]
+fn generic_over_blocking_clients() {
+  let server = MockServer::start(TOKEN);
+  let ureq = UreqClient::new(TOKEN.to_owned()).with_base_url(server.base_url());
+  let reqwest = ReqwestBlockingCLient::new(TOKEN.to_owned()).with_base_url(server.base_url());
+  assert_eq!(remaining_calls(&ureq), 38);
+  assert_eq!(remaining_calls(&reqwest), 38);
+  assert_eq!(remaining_calls(&FakeClient), 4);
+  // Test mode is optional for clients
+  assert!(FakeClient
+    .get_area_info_with_test("tshwane-6-brooklyn", TestMode::Current)
+    .is_err());
+  assert!(ureq
+    .get_area_info_with_test("tshwane-6-brooklyn", TestMode::Current)
+    .is_ok());
+}
+
+#[tokio::test]
+async fn generic_over_async_clients() {
+  let server = MockServer::start(TOKEN);
+  let api = ReqwestAsyncCLient::new(TOKEN.to_owned()).with_base_url(server.base_url());
+  assert_eq!(remaining_calls_async(&api).await, 38);
+}
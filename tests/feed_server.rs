@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use eskom_se_push_api::{
+  feed_server::{FeedConfig, FeedConfigBuilder, FeedServer},
+  mock_server::{MockResponse, MockServer},
+  status::EskomStatus,
+  ureq_client::UreqClient,
+};
+
+const TOKEN: &str = "test-token";
+
+fn start(upstream: &MockServer, config: FeedConfig) -> FeedServer {
+  let client = UreqClient::new(TOKEN.to_owned()).with_base_url(upstream.base_url());
+  FeedServer::start("127.0.0.1:0", client, config).unwrap()
+}
+
+fn brooklyn() -> FeedConfigBuilder {
+  let mut config = FeedConfigBuilder::default();
+  config.areas(vec!["tshwane-6-brooklyn".to_owned()]);
+  config
+}
+
+fn get(url: &str) -> (u16, String, String) {
+  match ureq::get(url).call() {
+    Ok(response) => (
+      response.status(),
+      response.content_type().to_owned(),
+      response.into_string().unwrap(),
+    ),
+    Err(ureq::Error::Status(status, response)) => (
+      status,
+      response.content_type().to_owned(),
+      response.into_string().unwrap(),
+    ),
+    Err(e) => panic!("{}", e),
+  }
+}
+
+#[test]
+fn serves_calendars_from_the_cache() {
+  let upstream = MockServer::start(TOKEN);
+  let server = start(&upstream, brooklyn().build().unwrap());
+  for _ in 0..5 {
+    let (status, content_type, body) = get(&server.calendar_url("tshwane-6-brooklyn"));
+    assert_eq!(status, 200);
+    assert_eq!(content_type, "text/calendar");
+    assert!(body.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert_eq!(body.matches("BEGIN:VEVENT").count(), 2);
+    assert!(body.contains("UID:tshwane-6-brooklyn-20220808T180000Z@eskom-se-push-api\r\n"));
+  }
+  assert_eq!(upstream.hits("area"), 1);
+  // Only projecting the schedule uses the status
+  assert_eq!(upstream.hits("status"), 0);
+
+  for _ in 0..3 {
+    let (status, _, body) = get(&format!("{}/status.json", server.base_url()));
+    assert_eq!(status, 200);
+    let status: EskomStatus = serde_json::from_str(&body).unwrap();
+    assert_eq!(status.eskom().stage, "3");
+  }
+  assert_eq!(upstream.hits("status"), 1);
+}
+
+#[test]
+fn projects_the_schedule() {
+  let upstream = MockServer::start(TOKEN);
+  let config = brooklyn().projected_days(2usize).build().unwrap();
+  let server = start(&upstream, config);
+  let (status, _, body) = get(&server.calendar_url("tshwane-6-brooklyn"));
+  assert_eq!(status, 200);
+  assert!(body.contains("STATUS:CONFIRMED"));
+  assert!(body.contains("STATUS:TENTATIVE"));
+  assert_eq!(upstream.hits("status"), 1);
+}
+
+#[test]
+fn only_serves_the_allowed_areas() {
+  let upstream = MockServer::start(TOKEN);
+  // The allow-list is required
+  assert!(FeedConfigBuilder::default().build().is_err());
+  let server = start(&upstream, brooklyn().build().unwrap());
+  assert_eq!(get(&server.calendar_url("eskde-10-fourways")).0, 404);
+  assert_eq!(get(&format!("{}/calendar/", server.base_url())).0, 404);
+  assert_eq!(get(&format!("{}/area", server.base_url())).0, 404);
+  assert_eq!(upstream.hits("area"), 0);
+  assert_eq!(get(&server.calendar_url("tshwane-6-brooklyn")).0, 200);
+
+  let config = FeedConfigBuilder::default()
+    .areas(Vec::new())
+    .build()
+    .unwrap();
+  let server = start(&upstream, config);
+  assert_eq!(get(&server.calendar_url("tshwane-6-brooklyn")).0, 404);
+  assert_eq!(upstream.hits("area"), 1);
+}
+
+#[test]
+fn bounds_the_calls_when_the_api_fails() {
+  let upstream = MockServer::start(TOKEN);
+  let config = brooklyn()
+    .area_ttl(Duration::from_millis(200))
+    .error_ttl(Duration::from_millis(200))
+    .build()
+    .unwrap();
+  let server = start(&upstream, config);
+  // The failure is cached so the subscribers don't call the API again until the error TTL expires
+  upstream.respond_with("area", MockResponse::status(429));
+  for _ in 0..3 {
+    let (status, _, body) = get(&server.calendar_url("tshwane-6-brooklyn"));
+    assert_eq!(status, 502);
+    assert!(body.contains("Too Many Requests"));
+  }
+  assert_eq!(upstream.hits("area"), 1);
+
+  upstream.reset();
+  std::thread::sleep(Duration::from_millis(250));
+  assert_eq!(get(&server.calendar_url("tshwane-6-brooklyn")).0, 200);
+  assert_eq!(upstream.hits("area"), 2);
+
+  // The last good calendar is served while the API is failing
+  upstream.respond_with("area", MockResponse::status(500));
+  std::thread::sleep(Duration::from_millis(250));
+  for _ in 0..3 {
+    assert_eq!(get(&server.calendar_url("tshwane-6-brooklyn")).0, 200);
+  }
+  assert_eq!(upstream.hits("area"), 3);
+}
@@ -0,0 +1,76 @@
+use eskom_se_push_api::{
+  errors::{APIError, HttpError},
+  mock_server::{MockResponse, MockServer},
+  reqwest_async_client::ReqwestAsyncCLient,
+};
+
+const TOKEN: &str = "test-token";
+
+fn client(server: &MockServer) -> ReqwestAsyncCLient {
+  ReqwestAsyncCLient::new(TOKEN.to_owned()).with_base_url(server.base_url())
+}
+
+#[tokio::test]
+async fn load_shedding_status() {
+  let server = MockServer::start(TOKEN);
+  let status = client(&server).get_load_shedding_status().await.unwrap();
+  assert_eq!(status.eskom().stage, "3");
+}
+
+#[tokio::test]
+async fn area_info() {
+  let server = MockServer::start(TOKEN);
+  let info = client(&server)
+    .get_area_info("tshwane-6-brooklyn")
+    .await
+    .unwrap();
+  assert_eq!(info.events.len(), 2);
+  assert_eq!(server.requests()[0].url, "/area?id=tshwane-6-brooklyn");
+}
+
+#[tokio::test]
+async fn check_allowance() {
+  let server = MockServer::start(TOKEN);
+  let allowance = client(&server).check_allowance().await.unwrap();
+  assert_eq!(allowance.allowance.count, 12);
+}
+
+#[tokio::test]
+async fn scripted_errors() {
+  let server = MockServer::start(TOKEN);
+  let api = client(&server);
+  server.respond_once("area", MockResponse::status(400));
+  server.respond_once("area", MockResponse::status(404));
+  assert!(matches!(
+    api.get_area_info("tshwane-6-brooklyn").await,
+    Err(HttpError::APIError {
+      error: APIError::BadRequest(_),
+      ..
+    })
+  ));
+  assert!(matches!(
+    api.get_area_info("tshwane-6-brooklyn").await,
+    Err(HttpError::APIError {
+      error: APIError::NotFound(_),
+      ..
+    })
+  ));
+}
+
+#[tokio::test]
+async fn parses_the_error_body() {
+  let server = MockServer::start(TOKEN);
+  server.respond_once(
+    "status",
+    MockResponse::new(403, r#"{"error": "token disabled"}"#),
+  );
+  let err = client(&server)
+    .get_load_shedding_status()
+    .await
+    .unwrap_err();
+  assert!(err.is_auth());
+  assert_eq!(
+    err.api_error().and_then(APIError::body).unwrap().error,
+    "token disabled"
+  );
+}
@@ -0,0 +1,105 @@
+use eskom_se_push_api::{
+  errors::{APIError, ConfigError, HttpError},
+  mock_server::{MockResponse, MockServer},
+  reqwest_blocking_client::ReqwestBlockingCLient,
+};
+
+const TOKEN: &str = "test-token";
+
+fn client(server: &MockServer) -> ReqwestBlockingCLient {
+  ReqwestBlockingCLient::new(TOKEN.to_owned()).with_base_url(server.base_url())
+}
+
+#[test]
+fn load_shedding_status() {
+  let server = MockServer::start(TOKEN);
+  let status = client(&server).get_load_shedding_status().unwrap();
+  assert_eq!(status.eskom().stage, "3");
+}
+
+#[test]
+fn area_info() {
+  let server = MockServer::start(TOKEN);
+  let info = client(&server).get_area_info("tshwane-6-brooklyn").unwrap();
+  assert_eq!(info.info.name, "Brooklyn (6)");
+  assert_eq!(server.requests()[0].url, "/area?id=tshwane-6-brooklyn");
+}
+
+#[test]
+fn areas_search() {
+  let server = MockServer::start(TOKEN);
+  let search = client(&server).areas_search("brooklyn").unwrap();
+  assert_eq!(search.areas.len(), 2);
+}
+
+#[test]
+fn check_allowance() {
+  let server = MockServer::start(TOKEN);
+  let allowance = client(&server).check_allowance().unwrap();
+  assert_eq!(allowance.allowance.type_field, "daily");
+  assert_eq!(server.requests()[0].token.as_deref(), Some(TOKEN));
+}
+
+#[test]
+fn scripted_errors() {
+  let server = MockServer::start(TOKEN);
+  let api = client(&server);
+  server.respond_once("api_allowance", MockResponse::status(403));
+  server.respond_once("api_allowance", MockResponse::status(429));
+  server.respond_once("api_allowance", MockResponse::status(500));
+  assert!(matches!(
+    api.check_allowance(),
+    Err(HttpError::APIError {
+      error: APIError::Forbidden(_),
+      ..
+    })
+  ));
+  assert!(matches!(
+    api.check_allowance(),
+    Err(HttpError::APIError {
+      error: APIError::TooManyRequests(_),
+      ..
+    })
+  ));
+  assert!(matches!(
+    api.check_allowance(),
+    Err(HttpError::APIError {
+      error: APIError::ServerError(_),
+      ..
+    })
+  ));
+  assert!(api.check_allowance().is_ok());
+}
+
+#[test]
+fn try_new_rejects_invalid_tokens() {
+  assert!(matches!(
+    ReqwestBlockingCLient::try_new("bad\ntoken".to_owned()),
+    Err(ConfigError::InvalidHeader { .. })
+  ));
+  assert!(ReqwestBlockingCLient::try_new(TOKEN.to_owned()).is_ok());
+}
+
+#[test]
+fn errors_carry_context() {
+  let server = MockServer::start(TOKEN);
+  let api = client(&server);
+  server.respond_once("status", MockResponse::status(503));
+  let err = api.get_load_shedding_status().unwrap_err();
+  assert_eq!(err.status(), Some(http::StatusCode::SERVICE_UNAVAILABLE));
+  assert!(err
+    .context()
+    .unwrap()
+    .url
+    .as_deref()
+    .unwrap()
+    .ends_with("/status"));
+  assert!(err.is_retryable());
+
+  let base_url = server.base_url();
+  drop(server);
+  let api = ReqwestBlockingCLient::new(TOKEN.to_owned()).with_base_url(base_url);
+  let err = api.check_allowance().unwrap_err();
+  assert!(matches!(err, HttpError::NoInternet { .. }));
+  assert!(std::error::Error::source(&err).is_some());
+}
@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use eskom_se_push_api::{
+  errors::{APIError, HttpError},
+  mock_server::{MockResponse, MockServer},
+  reqwest_async_client::ReqwestAsyncCLient,
+  reqwest_blocking_client::ReqwestBlockingCLient,
+  retry::{RetryOn, RetryPolicy, RetryPolicyBuilder},
+  ureq_client::UreqClient,
+};
+
+const TOKEN: &str = "test-token";
+
+fn policy() -> RetryPolicy {
+  RetryPolicyBuilder::default()
+    .initial_backoff(Duration::from_millis(1))
+    .retry_on(vec![RetryOn::ServerError, RetryOn::TooManyRequests])
+    .build()
+    .unwrap()
+}
+
+#[test]
+fn retries_server_errors() {
+  let server = MockServer::start(TOKEN);
+  let api = UreqClient::new(TOKEN.to_owned())
+    .with_base_url(server.base_url())
+    .with_retry_policy(policy());
+  server.respond_once("status", MockResponse::status(500));
+  server.respond_once("status", MockResponse::status(503));
+  assert!(api.get_load_shedding_status().is_ok());
+  assert_eq!(server.hits("status"), 3);
+}
+
+#[test]
+fn gives_up_after_max_attempts() {
+  let server = MockServer::start(TOKEN);
+  let api = ReqwestBlockingCLient::new(TOKEN.to_owned())
+    .with_base_url(server.base_url())
+    .with_retry_policy(policy());
+  server.respond_with("status", MockResponse::status(502));
+  assert!(matches!(
+    api.get_load_shedding_status(),
+    Err(HttpError::APIError {
+      error: APIError::ServerError(_),
+      ..
+    })
+  ));
+  assert_eq!(server.hits("status"), 3);
+}
+
+#[test]
+fn never_retries_bad_request_or_forbidden() {
+  let server = MockServer::start(TOKEN);
+  let api = UreqClient::new(TOKEN.to_owned())
+    .with_base_url(server.base_url())
+    .with_retry_policy(policy());
+  server.respond_once("status", MockResponse::status(400));
+  assert!(api.get_load_shedding_status().is_err());
+  server.respond_once("status", MockResponse::status(403));
+  assert!(api.get_load_shedding_status().is_err());
+  assert_eq!(server.hits("status"), 2);
+}
+
+#[test]
+fn honours_retry_after() {
+  let server = MockServer::start(TOKEN);
+  let api = UreqClient::new(TOKEN.to_owned())
+    .with_base_url(server.base_url())
+    .with_retry_policy(policy());
+  server.respond_once(
+    "api_allowance",
+    MockResponse::status(429).with_header("Retry-After", "0"),
+  );
+  server.respond_once(
+    "status",
+    MockResponse::status(429).with_header("Retry-After", "3600"),
+  );
+  assert!(api.check_allowance().is_ok());
+  assert_eq!(server.hits("api_allowance"), 2);
+  assert!(matches!(
+    api.get_load_shedding_status(),
+    Err(HttpError::APIError {
+      error: APIError::TooManyRequests(_),
+      ..
+    })
+  ));
+  assert_eq!(server.hits("status"), 1);
+}
+
+#[tokio::test]
+async fn retries_async_client() {
+  let server = MockServer::start(TOKEN);
+  let api = ReqwestAsyncCLient::new(TOKEN.to_owned())
+    .with_base_url(server.base_url())
+    .with_retry_policy(policy());
+  server.respond_once("area", MockResponse::status(500));
+  assert!(api.get_area_info("tshwane-6-brooklyn").await.is_ok());
+  assert_eq!(server.hits("area"), 2);
+}
@@ -0,0 +1,83 @@
+use std::sync::Mutex;
+
+use bytes::Bytes;
+use eskom_se_push_api::{
+  allowance::AllowanceCheckURL,
+  constants::TOKEN_KEY,
+  errors::{APIError, HttpError},
+  mock_server::fixtures,
+  transport::Transport,
+  Endpoint,
+};
+
+/// A transport that records the requests and returns a fixed response
+struct FixedTransport {
+  status: u16,
+  body: &'static str,
+  requests: Mutex<Vec<http::Request<Bytes>>>,
+}
+
+impl FixedTransport {
+  fn new(status: u16, body: &'static str) -> Self {
+    FixedTransport {
+      status,
+      body,
+      requests: Mutex::new(Vec::new()),
+    }
+  }
+}
+
+impl Transport for FixedTransport {
+  fn send(&self, request: http::Request<Bytes>) -> Result<http::Response<Bytes>, HttpError> {
+    self.requests.lock().unwrap().push(request);
+    Ok(
+      http::Response::builder()
+        .status(self.status)
+        .body(Bytes::from_static(self.body.as_bytes()))?,
+    )
+  }
+}
+
+#[test]
+fn query_adds_token_and_parses_response() {
+  let transport = FixedTransport::new(200, fixtures::ALLOWANCE);
+  let allowance = AllowanceCheckURL::default()
+    .query(&transport, "test-token")
+    .unwrap();
+  assert_eq!(allowance.allowance.limit, 50);
+
+  let requests = transport.requests.lock().unwrap();
+  assert_eq!(requests[0].method(), "GET");
+  assert_eq!(
+    requests[0].uri(),
+    "https://developer.sepush.co.za/business/2.0/api_allowance"
+  );
+  assert_eq!(requests[0].headers()[TOKEN_KEY], "test-token");
+}
+
+#[test]
+fn query_maps_error_status() {
+  let transport = FixedTransport::new(429, "{}");
+  assert!(matches!(
+    AllowanceCheckURL::default().query(&transport, "test-token"),
+    Err(HttpError::APIError {
+      error: APIError::TooManyRequests(_),
+      ..
+    })
+  ));
+}
+
+#[test]
+fn server_errors_hold_the_start_of_the_body() {
+  let transport = FixedTransport::new(500, Box::leak("x".repeat(10_000).into_boxed_str()));
+  match AllowanceCheckURL::default().query(&transport, "test-token") {
+    Err(HttpError::APIError {
+      error: APIError::ServerError(body),
+      context,
+    }) => {
+      assert!(body.len() < 1_000);
+      assert_eq!(Some(body), context.body);
+    }
+    other => panic!("expected a server error, got {:?}", other.map(|_| ())),
+  }
+}
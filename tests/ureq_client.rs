@@ -0,0 +1,276 @@
+use eskom_se_push_api::{
+  area_info::TestMode,
+  errors::{APIError, ConfigError, HttpError},
+  mock_server::{MockResponse, MockServer},
+  status::Stage,
+  ureq_client::UreqClient,
+  EskomClient,
+};
+
+const TOKEN: &str = "test-token";
+
+fn client(server: &MockServer) -> UreqClient {
+  UreqClient::new(TOKEN.to_owned()).with_base_url(server.base_url())
+}
+
+#[test]
+fn load_shedding_status() {
+  let server = MockServer::start(TOKEN);
+  let status = client(&server).get_load_shedding_status().unwrap();
+  assert_eq!(status.eskom().stage, "3");
+  assert_eq!(status.area("capetown").unwrap().next_stages.len(), 2);
+}
+
+#[test]
+fn area_info() {
+  let server = MockServer::start(TOKEN);
+  let info = client(&server).get_area_info("tshwane-6-brooklyn").unwrap();
+  assert_eq!(info.info.region, "City of Tshwane");
+  assert_eq!(info.events.len(), 2);
+  assert_eq!(info.events[0].note, Stage::Stage2);
+  assert_eq!(info.events[0].duration(), chrono::Duration::minutes(150));
+  assert!(info.events[0].start < info.events[1].start);
+  assert_eq!(info.schedule.days.len(), 2);
+  let days = info.schedule.parse_days().unwrap();
+  assert_eq!(days[0].weekday, chrono::Weekday::Mon);
+  assert!(days[1]
+    .stages
+    .iter()
+    .flatten()
+    .any(|range| range.crosses_midnight()));
+  assert_eq!(server.requests()[0].url, "/area?id=tshwane-6-brooklyn");
+}
+
+#[test]
+fn area_info_test_mode() {
+  let server = MockServer::start(TOKEN);
+  let info = client(&server)
+    .get_area_info_with_test("tshwane-6-brooklyn", TestMode::Current)
+    .unwrap();
+  assert_eq!(info.info.region, "City of Tshwane");
+  assert_eq!(
+    server.requests()[0].url,
+    "/area?id=tshwane-6-brooklyn&test=current"
+  );
+}
+
+#[test]
+fn areas_search() {
+  let server = MockServer::start(TOKEN);
+  let search = client(&server).areas_search("brooklyn").unwrap();
+  assert_eq!(search.areas[0].id, "tshwane-6-brooklyn");
+  assert_eq!(server.requests()[0].url, "/areas_search?text=brooklyn");
+}
+
+#[test]
+fn areas_search_encodes_the_text() {
+  let server = MockServer::start(TOKEN);
+  client(&server)
+    .areas_search("Cape Town & Surrounds")
+    .unwrap();
+  assert_eq!(
+    server.requests()[0].url,
+    "/areas_search?text=Cape+Town+%26+Surrounds"
+  );
+}
+
+#[test]
+fn areas_nearby_sends_both_coordinates() {
+  let server = MockServer::start(TOKEN);
+  client(&server).areas_nearby(-25.7479, 28.2293).unwrap();
+  client(&server).topics_nearby(0.0, 28.2293).unwrap();
+  let requests = server.requests();
+  assert_eq!(requests[0].url, "/areas_nearby?lat=-25.7479&long=28.2293");
+  assert_eq!(requests[1].url, "/topics_nearby?lat=0&long=28.2293");
+}
+
+#[test]
+fn rejects_invalid_coordinates() {
+  let server = MockServer::start(TOKEN);
+  assert!(matches!(
+    client(&server).areas_nearby(-125.7479, 28.2293),
+    Err(HttpError::InvalidCoordinates {
+      latitude,
+      longitude
+    }) if latitude == -125.7479 && longitude == 28.2293
+  ));
+  assert!(client(&server).topics_nearby(-25.7479, 228.2293).is_err());
+  assert!(server.requests().is_empty());
+}
+
+#[test]
+fn check_allowance() {
+  let server = MockServer::start(TOKEN);
+  let allowance = client(&server).check_allowance().unwrap();
+  assert_eq!(allowance.allowance.count, 12);
+  assert_eq!(allowance.allowance.limit, 50);
+  assert_eq!(server.requests()[0].token.as_deref(), Some(TOKEN));
+}
+
+#[test]
+fn invalid_token_is_forbidden() {
+  let server = MockServer::start(TOKEN);
+  let api = UreqClient::new("wrong-token".to_owned()).with_base_url(server.base_url());
+  assert!(matches!(
+    api.check_allowance(),
+    Err(HttpError::APIError {
+      error: APIError::Forbidden(_),
+      ..
+    })
+  ));
+}
+
+#[test]
+fn scripted_errors() {
+  let server = MockServer::start(TOKEN);
+  let api = client(&server);
+  server.respond_once("status", MockResponse::status(400));
+  server.respond_once("status", MockResponse::status(404));
+  server.respond_once("status", MockResponse::status(429));
+  server.respond_once("status", MockResponse::status(503));
+  assert!(matches!(
+    api.get_load_shedding_status(),
+    Err(HttpError::APIError {
+      error: APIError::BadRequest(_),
+      ..
+    })
+  ));
+  assert!(matches!(
+    api.get_load_shedding_status(),
+    Err(HttpError::APIError {
+      error: APIError::NotFound(_),
+      ..
+    })
+  ));
+  assert!(matches!(
+    api.get_load_shedding_status(),
+    Err(HttpError::APIError {
+      error: APIError::TooManyRequests(_),
+      ..
+    })
+  ));
+  assert!(matches!(
+    api.get_load_shedding_status(),
+    Err(HttpError::APIError {
+      error: APIError::ServerError(_),
+      ..
+    })
+  ));
+  assert!(api.get_load_shedding_status().is_ok());
+  assert_eq!(server.hits("status"), 5);
+}
+
+#[test]
+fn unreachable_server() {
+  let server = MockServer::start(TOKEN);
+  let base_url = server.base_url();
+  drop(server);
+  let api = UreqClient::new(TOKEN.to_owned()).with_base_url(base_url);
+  assert!(matches!(
+    api.check_allowance(),
+    Err(HttpError::NoInternet { .. })
+  ));
+}
+
+#[test]
+fn try_constructors_return_config_errors() {
+  assert!(matches!(
+    UreqClient::try_new("bad\ntoken".to_owned()),
+    Err(ConfigError::InvalidHeader { .. })
+  ));
+  assert!(UreqClient::try_new(TOKEN.to_owned()).is_ok());
+  match UreqClient::try_from_env(Some("ESKOMSEPUSH_TEST_MISSING_TOKEN")) {
+    Err(ConfigError::MissingVariable { name, .. }) => {
+      assert_eq!(name, "ESKOMSEPUSH_TEST_MISSING_TOKEN")
+    }
+    _ => panic!("expected a missing variable error"),
+  }
+}
+
+#[test]
+fn errors_carry_context() {
+  let server = MockServer::start(TOKEN);
+  let api = client(&server);
+  server.respond_once("area", MockResponse::status(404));
+  let err = api.get_area_info("tshwane-6-brooklyn").unwrap_err();
+  let context = err.context().unwrap();
+  assert_eq!(err.status(), Some(http::StatusCode::NOT_FOUND));
+  assert_eq!(
+    context.url.as_deref(),
+    Some(format!("{}/area?id=tshwane-6-brooklyn", server.base_url()).as_str())
+  );
+  assert_eq!(context.body.as_deref(), Some(r#"{"error": "Not Found"}"#));
+  assert!(!err.is_retryable());
+
+  server.respond_once("status", MockResponse::status(418));
+  let err = api.get_load_shedding_status().unwrap_err();
+  assert!(matches!(err, HttpError::UnexpectedResponse { .. }));
+  assert_eq!(err.status(), Some(http::StatusCode::IM_A_TEAPOT));
+
+  server.respond_once("status", MockResponse::new(200, "not json"));
+  let err = api.get_load_shedding_status().unwrap_err();
+  assert!(matches!(err, HttpError::InvalidResponse { .. }));
+  assert!(std::error::Error::source(&err).is_some());
+
+  server.respond_once("status", MockResponse::status(429));
+  let err = api.get_load_shedding_status().unwrap_err();
+  assert!(err.is_quota() && err.is_retryable() && !err.is_auth());
+}
+
+#[test]
+fn parses_the_error_body() {
+  let server = MockServer::start(TOKEN);
+  let api = client(&server);
+  server.respond_once(
+    "area",
+    MockResponse::new(400, r#"{"error": "invalid area id"}"#),
+  );
+  let err = api.get_area_info("nowhere").unwrap_err();
+  let body = err.api_error().and_then(APIError::body).unwrap();
+  assert_eq!(body.error, "invalid area id");
+  assert!(err.to_string().contains("Bad Request (invalid area id)"));
+
+  server.respond_once("area", MockResponse::new(400, "<html>Bad Request</html>"));
+  let err = api.get_area_info("nowhere").unwrap_err();
+  assert!(matches!(
+    err,
+    HttpError::APIError {
+      error: APIError::BadRequest(None),
+      ..
+    }
+  ));
+}
+
+#[test]
+fn agent_transport_keeps_repeated_headers() {
+  use eskom_se_push_api::{
+    allowance::AllowanceCheckURLBuilder, constants::TOKEN_KEY, transport::Transport, Endpoint,
+  };
+
+  let server = MockServer::start(TOKEN);
+  let mut request = http::Request::builder()
+    .uri(format!("{}/api_allowance", server.base_url()))
+    .header("Accept", "application/json")
+    .header("Accept", "text/plain")
+    .body(bytes::Bytes::new())
+    .unwrap();
+  request
+    .headers_mut()
+    .insert(TOKEN_KEY, http::HeaderValue::from_static(TOKEN));
+  assert_eq!(ureq::agent().send(request).unwrap().status(), 200);
+  let headers = &server.requests()[0].headers;
+  assert!(headers
+    .iter()
+    .any(|(name, value)| name.eq_ignore_ascii_case("accept")
+      && value == "application/json, text/plain"));
+
+  // Without the token in the default headers the call is rejected by the API
+  let err = AllowanceCheckURLBuilder::default()
+    .base_url(server.base_url())
+    .build()
+    .unwrap()
+    .ureq_client(&ureq::agent())
+    .unwrap_err();
+  assert!(err.is_auth());
+  assert!(err.context().unwrap().url.is_some());
+}